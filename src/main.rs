@@ -2,75 +2,116 @@ use avian3d::PhysicsPlugins;
 use bevy::prelude::*;
 
 use crate::plugins::{
+    accessibility::AccessibilityPlugin,
+    camera::CameraPlugin,
+    difficulty::DifficultyPlugin,
+    endless::EndlessPlugin,
     enemy::EnemyPlugin,
+    game_over::GameOverPlugin,
+    glyphs::GlyphPlugin,
     gun::GunPlugin,
     health::{Health, HealthPlugin},
+    highscore::HighScorePlugin,
+    level::{Level, LevelPlugin},
+    locale::LocalePlugin,
+    main_menu::MainMenuPlugin,
+    menu::MenuPlugin,
+    minimap::MinimapPlugin,
+    model::ModelPlugin,
+    music::MusicPlugin,
+    net::NetPlugin,
+    particles::ParticlePlugin,
+    pause::PausePlugin,
+    pickup::PickupPlugin,
     player::PlayerPlugin,
+    powerup::PowerUpPlugin,
+    settings::SettingsPlugin,
+    shop::ShopPlugin,
+    sound::SoundPlugin,
+    stats::StatsPlugin,
+    tutorial::TutorialPlugin,
     ui::GameUiPlugin,
+    upgrade::UpgradePlugin,
+    wave::WavePlugin,
 };
 
 mod plugins;
 
 fn main() {
     App::new()
+        .add_plugins(DefaultPlugins)
+        .init_state::<AppState>()
         .add_plugins((
-            DefaultPlugins,
             PlayerPlugin,
             EnemyPlugin,
             GunPlugin,
             HealthPlugin,
-            GameUiPlugin,
+            UpgradePlugin,
+            WavePlugin,
+            PickupPlugin,
+            PowerUpPlugin,
+            NetPlugin,
+            StatsPlugin,
+            DifficultyPlugin,
+            EndlessPlugin,
             PhysicsPlugins::default(),
             // PhysicsDebugPlugin::default(),
             // FpsOverlayPlugin::default(),
         ))
-        .add_systems(Startup, setup)
-        .add_systems(FixedUpdate, cleanup_sfx)
+        .add_plugins((
+            GameUiPlugin,
+            MenuPlugin,
+            ShopPlugin,
+            HighScorePlugin,
+            MainMenuPlugin,
+            GameOverPlugin,
+            SettingsPlugin,
+            SoundPlugin,
+            MusicPlugin,
+            ModelPlugin,
+            LevelPlugin,
+            CameraPlugin,
+            ParticlePlugin,
+            PausePlugin,
+            TutorialPlugin,
+        ))
+        .add_plugins((
+            LocalePlugin,
+            AccessibilityPlugin,
+            GlyphPlugin,
+            MinimapPlugin,
+        ))
+        .add_systems(OnEnter(AppState::InGame), setup)
         .run();
 }
 
-#[derive(Component)]
-pub struct Cpu;
+#[derive(States, Default, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AppState {
+    #[default]
+    MainMenu,
+    /// A run is in progress, everything spawned for it is despawned on exit.
+    InGame,
+    GameOver,
+}
 
 #[derive(Component)]
-pub struct DespawnOnFinish;
-
-fn cleanup_sfx(mut commands: Commands, query: Query<(Entity, &AudioSink), With<DespawnOnFinish>>) {
-    for (entity, sink) in &query {
-        if sink.empty() {
-            commands.entity(entity).despawn();
-        }
-    }
-}
+pub struct Cpu;
 
-fn setup(
-    mut commands: Commands,
-    mut meshes: ResMut<Assets<Mesh>>,
-    mut materials: ResMut<Assets<StandardMaterial>>,
-) {
+fn setup(mut commands: Commands, level: Res<Level>) {
     commands.spawn((
-        Mesh3d(meshes.add(Circle::new(4.0))),
-        MeshMaterial3d(materials.add(Color::WHITE)),
-        Transform::from_rotation(Quat::from_rotation_x(-std::f32::consts::FRAC_PI_2)),
+        DespawnOnExit(AppState::InGame),
+        Cpu,
+        Health(100.),
+        Transform::from_translation(level.cpu),
     ));
 
-    commands.spawn((Cpu, Health(100.)));
-    // commands.spawn((
-    //     SceneRoot(asset_server.load(GltfAssetLabel::Scene(0).from_asset("Chara.glb"))),
-    //     Transform::from_scale(Vec3::splat(3.)).with_translation(Vec3::ZERO),
-    // ));
-
     // light
     commands.spawn((
+        DespawnOnExit(AppState::InGame),
         PointLight {
             shadows_enabled: false,
             ..default()
         },
         Transform::from_xyz(4.0, 8.0, 4.0),
     ));
-    // camera
-    commands.spawn((
-        Camera3d::default(),
-        Transform::from_xyz(0., 40., 0.).looking_at(Vec3::ZERO, Vec3::Y),
-    ));
 }
@@ -0,0 +1,193 @@
+//! Color palettes and high-contrast outlines, both picked in the settings.
+//!
+//! Gameplay and HUD colors are looked up by what they stand for, a [`Swatch`], in the chosen
+//! [`Palette`]. Materials are repainted by the plugin owning them, UI nodes through [`Painted`].
+
+use bevy::{prelude::*, ui::UiSystems};
+use serde::{Deserialize, Serialize};
+
+use crate::plugins::{enemy::EnemyKind, powerup::PowerUpKind, settings::Settings};
+
+pub struct AccessibilityPlugin;
+
+#[derive(Serialize, Deserialize, Default, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Palette {
+    #[default]
+    Standard,
+    /// Tells colors apart by blue/orange and lightness, for deuteranopia and protanopia.
+    RedGreen,
+    /// Tells colors apart by red/cyan and lightness, for tritanopia.
+    BlueYellow,
+}
+
+pub const PALETTES: &[Palette] = &[Palette::Standard, Palette::RedGreen, Palette::BlueYellow];
+
+/// What a color stands for.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum Swatch {
+    Enemy(EnemyKind),
+    PowerUp(PowerUpKind),
+    /// Player seat number, starting at 1.
+    Player(usize),
+    Bullet,
+    Coin,
+    /// The CPU and its health bar.
+    Cpu,
+    Spawner,
+}
+
+impl Palette {
+    /// Locale key of the palette's name.
+    pub fn key(self) -> &'static str {
+        match self {
+            Palette::Standard => "palette.standard",
+            Palette::RedGreen => "palette.red_green",
+            Palette::BlueYellow => "palette.blue_yellow",
+        }
+    }
+
+    fn players(self) -> [Color; 4] {
+        match self {
+            Palette::Standard => [
+                Color::srgb_u8(124, 144, 255),
+                Color::srgb_u8(255, 150, 60),
+                Color::srgb_u8(120, 230, 110),
+                Color::srgb_u8(240, 110, 220),
+            ],
+            Palette::RedGreen => [
+                Color::srgb_u8(86, 180, 233),
+                Color::srgb_u8(230, 159, 0),
+                Color::srgb_u8(0, 158, 115),
+                Color::srgb_u8(255, 255, 255),
+            ],
+            Palette::BlueYellow => [
+                Color::srgb_u8(0, 200, 220),
+                Color::srgb_u8(255, 130, 160),
+                Color::srgb_u8(255, 255, 255),
+                Color::srgb_u8(150, 150, 255),
+            ],
+        }
+    }
+
+    pub fn color(self, swatch: Swatch) -> Color {
+        match (self, swatch) {
+            (_, Swatch::Player(number)) => {
+                let players = self.players();
+                players[(number.max(1) - 1) % players.len()]
+            }
+
+            (Palette::Standard, Swatch::Enemy(EnemyKind::Grunt)) => Color::srgb_u8(255, 50, 50),
+            (Palette::Standard, Swatch::Enemy(EnemyKind::Runner)) => Color::srgb_u8(255, 140, 40),
+            (Palette::Standard, Swatch::Enemy(EnemyKind::Tank)) => Color::srgb_u8(150, 20, 60),
+            (Palette::Standard, Swatch::PowerUp(kind)) => match kind {
+                PowerUpKind::DoubleDamage => Color::srgb_u8(255, 90, 40),
+                PowerUpKind::RapidFire => Color::srgb_u8(255, 240, 60),
+                PowerUpKind::SpeedBoost => Color::srgb_u8(60, 255, 120),
+                PowerUpKind::Shield => Color::srgb_u8(80, 160, 255),
+                PowerUpKind::Bomb => Color::srgb_u8(220, 60, 255),
+            },
+            (Palette::Standard, Swatch::Bullet) => Color::srgb_u8(255, 222, 33),
+            (Palette::Standard, Swatch::Coin) => Color::srgb_u8(255, 200, 40),
+            (Palette::Standard, Swatch::Cpu) => Color::srgb_u8(0, 200, 0),
+            (Palette::Standard, Swatch::Spawner) => Color::srgb_u8(170, 60, 220),
+
+            (Palette::RedGreen, Swatch::Enemy(EnemyKind::Grunt)) => Color::srgb_u8(213, 94, 0),
+            (Palette::RedGreen, Swatch::Enemy(EnemyKind::Runner)) => Color::srgb_u8(204, 121, 167),
+            (Palette::RedGreen, Swatch::Enemy(EnemyKind::Tank)) => Color::srgb_u8(110, 40, 90),
+            (Palette::RedGreen, Swatch::PowerUp(kind)) => match kind {
+                PowerUpKind::DoubleDamage => Color::srgb_u8(213, 94, 0),
+                PowerUpKind::RapidFire => Color::srgb_u8(240, 228, 66),
+                PowerUpKind::SpeedBoost => Color::srgb_u8(0, 158, 115),
+                PowerUpKind::Shield => Color::srgb_u8(0, 114, 178),
+                PowerUpKind::Bomb => Color::srgb_u8(204, 121, 167),
+            },
+            (Palette::RedGreen, Swatch::Bullet) => Color::srgb_u8(86, 180, 233),
+            (Palette::RedGreen, Swatch::Coin) => Color::srgb_u8(240, 228, 66),
+            (Palette::RedGreen, Swatch::Cpu) => Color::srgb_u8(0, 114, 178),
+            (Palette::RedGreen, Swatch::Spawner) => Color::srgb_u8(230, 230, 230),
+
+            (Palette::BlueYellow, Swatch::Enemy(EnemyKind::Grunt)) => Color::srgb_u8(220, 40, 40),
+            (Palette::BlueYellow, Swatch::Enemy(EnemyKind::Runner)) => {
+                Color::srgb_u8(255, 130, 160)
+            }
+            (Palette::BlueYellow, Swatch::Enemy(EnemyKind::Tank)) => Color::srgb_u8(110, 0, 30),
+            (Palette::BlueYellow, Swatch::PowerUp(kind)) => match kind {
+                PowerUpKind::DoubleDamage => Color::srgb_u8(220, 40, 40),
+                PowerUpKind::RapidFire => Color::srgb_u8(255, 255, 255),
+                PowerUpKind::SpeedBoost => Color::srgb_u8(0, 200, 220),
+                PowerUpKind::Shield => Color::srgb_u8(60, 60, 255),
+                PowerUpKind::Bomb => Color::srgb_u8(255, 130, 160),
+            },
+            (Palette::BlueYellow, Swatch::Bullet) => Color::srgb_u8(0, 200, 220),
+            (Palette::BlueYellow, Swatch::Coin) => Color::srgb_u8(255, 200, 40),
+            (Palette::BlueYellow, Swatch::Cpu) => Color::srgb_u8(0, 160, 160),
+            (Palette::BlueYellow, Swatch::Spawner) => Color::srgb_u8(230, 230, 230),
+        }
+    }
+}
+
+/// Node color following the palette.
+#[derive(Component, Clone, Copy, Debug)]
+pub enum Painted {
+    Background(Swatch),
+    Border(Swatch),
+    Text(Swatch),
+}
+
+/// Node outlined while high contrast is on.
+#[derive(Component, Default)]
+pub struct ContrastOutline;
+
+const CONTRAST_OUTLINE_COLOR: Color = Color::WHITE;
+
+impl Plugin for AccessibilityPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            PostUpdate,
+            (update_painted_nodes, update_contrast_outlines).before(UiSystems::Prepare),
+        );
+    }
+}
+
+fn update_painted_nodes(
+    settings: Res<Settings>,
+    nodes: Query<(Entity, Ref<Painted>)>,
+    mut commands: Commands,
+) {
+    for (entity, painted) in &nodes {
+        if !settings.is_changed() && !painted.is_added() {
+            continue;
+        }
+        let mut entity = commands.entity(entity);
+        match *painted {
+            Painted::Background(swatch) => {
+                entity.insert(BackgroundColor(settings.palette.color(swatch)));
+            }
+            Painted::Border(swatch) => {
+                entity.insert(BorderColor::all(settings.palette.color(swatch)));
+            }
+            Painted::Text(swatch) => {
+                entity.insert(TextColor(settings.palette.color(swatch)));
+            }
+        }
+    }
+}
+
+fn update_contrast_outlines(
+    settings: Res<Settings>,
+    nodes: Query<(Entity, Ref<ContrastOutline>)>,
+    mut commands: Commands,
+) {
+    for (entity, outline) in &nodes {
+        if !settings.is_changed() && !outline.is_added() {
+            continue;
+        }
+        if settings.high_contrast {
+            commands
+                .entity(entity)
+                .insert(Outline::new(px(2), px(0), CONTRAST_OUTLINE_COLOR));
+        } else {
+            commands.entity(entity).remove::<Outline>();
+        }
+    }
+}
@@ -0,0 +1,86 @@
+//! Keeps UI nodes in sync with the gameplay components they display.
+//!
+//! A node declares what it shows with a [`Binding`] to another entity's component, and
+//! [`update_bindings`] rewrites it whenever that component changes. Gameplay systems only ever
+//! touch their own components.
+
+use bevy::prelude::*;
+
+use crate::plugins::locale::Locale;
+
+type TextFormat<C> = Box<dyn Fn(&C, &Locale) -> String + Send + Sync>;
+
+/// How a [`Binding`] turns its source component into something on screen.
+enum BoundField<C> {
+    /// Replaces the node's [`Text`], also rewritten when the language changes.
+    Text(TextFormat<C>),
+    /// Sets the node's width, in percent of its parent.
+    Width(Box<dyn Fn(&C) -> f32 + Send + Sync>),
+}
+
+/// Displays a field of the `C` component of `source` on this node.
+#[derive(Component)]
+pub struct Binding<C: Component> {
+    source: Entity,
+    field: BoundField<C>,
+}
+
+impl<C: Component> Binding<C> {
+    pub fn text(
+        source: Entity,
+        format: impl Fn(&C, &Locale) -> String + Send + Sync + 'static,
+    ) -> Self {
+        Self {
+            source,
+            field: BoundField::Text(Box::new(format)),
+        }
+    }
+
+    pub fn width(source: Entity, percent: impl Fn(&C) -> f32 + Send + Sync + 'static) -> Self {
+        Self {
+            source,
+            field: BoundField::Width(Box::new(percent)),
+        }
+    }
+}
+
+type BoundNodes<'w, 's, C> = Query<
+    'w,
+    's,
+    (
+        Ref<'static, Binding<C>>,
+        Option<&'static mut Text>,
+        Option<&'static mut Node>,
+    ),
+>;
+
+/// Refreshes nodes bound to a `C` that changed, and nodes that were just bound.
+pub fn update_bindings<C: Component>(
+    sources: Query<Ref<C>>,
+    locale: Res<Locale>,
+    mut nodes: BoundNodes<C>,
+) {
+    for (binding, text, node) in &mut nodes {
+        let Ok(source) = sources.get(binding.source) else {
+            continue;
+        };
+        if !source.is_changed() && !binding.is_added() && !locale.is_changed() {
+            continue;
+        }
+        match (&binding.field, text, node) {
+            (BoundField::Text(format), Some(mut text), _) => {
+                let value = format(&source, &locale);
+                if text.0 != value {
+                    text.0 = value;
+                }
+            }
+            (BoundField::Width(value), _, Some(mut node)) => {
+                let width = percent(value(&source));
+                if node.width != width {
+                    node.width = width;
+                }
+            }
+            _ => {}
+        }
+    }
+}
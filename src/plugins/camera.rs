@@ -0,0 +1,124 @@
+use bevy::prelude::*;
+use rand::Rng;
+
+use crate::{
+    AppState, Cpu,
+    plugins::{enemy::Enemy, health::Health, player::Player, settings::Settings},
+};
+
+pub struct CameraPlugin;
+
+#[derive(Component)]
+pub struct GameCamera;
+
+/// Camera height when everything framed is close together.
+const MIN_HEIGHT: f32 = 22.;
+const MAX_HEIGHT: f32 = 48.;
+/// Height per unit of distance between the framed points and their center.
+const HEIGHT_PER_UNIT: f32 = 2.2;
+/// Enemies closer than this to the CPU are kept in frame.
+const THREAT_RADIUS: f32 = 16.;
+/// How quickly the camera catches up with its target, higher is snappier.
+const FOLLOW_SHARPNESS: f32 = 4.;
+
+/// Trauma added per point of CPU damage, shake strength is the square of trauma.
+const TRAUMA_PER_DAMAGE: f32 = 0.04;
+const TRAUMA_DECAY: f32 = 1.5;
+const MAX_SHAKE_OFFSET: f32 = 0.8;
+
+#[derive(Resource, Default)]
+struct CameraShake {
+    trauma: f32,
+    /// Offset applied last frame, taken out again before following.
+    offset: Vec3,
+    last_cpu_health: Option<f32>,
+}
+
+impl Plugin for CameraPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<CameraShake>()
+            .add_systems(Startup, spawn_camera)
+            .add_systems(OnEnter(AppState::InGame), reset_camera)
+            .add_systems(
+                PostUpdate,
+                (add_cpu_trauma, follow_targets)
+                    .chain()
+                    .before(TransformSystems::Propagate)
+                    .run_if(in_state(AppState::InGame)),
+            );
+    }
+}
+
+fn default_transform() -> Transform {
+    Transform::from_xyz(0., 40., 0.).looking_at(Vec3::ZERO, Vec3::Y)
+}
+
+fn spawn_camera(mut commands: Commands) {
+    commands.spawn((GameCamera, Camera3d::default(), default_transform()));
+}
+
+fn reset_camera(
+    mut camera: Single<&mut Transform, With<GameCamera>>,
+    mut shake: ResMut<CameraShake>,
+) {
+    **camera = default_transform();
+    *shake = CameraShake::default();
+}
+
+fn add_cpu_trauma(cpu: Query<&Health, With<Cpu>>, mut shake: ResMut<CameraShake>) {
+    let Ok(health) = cpu.single() else {
+        return;
+    };
+    if let Some(last) = shake.last_cpu_health
+        && health.0 < last
+    {
+        shake.trauma = (shake.trauma + (last - health.0) * TRAUMA_PER_DAMAGE).min(1.);
+    }
+    shake.last_cpu_health = Some(health.0);
+}
+
+/// Frames the players, the CPU and the enemies closing in on it, then adds the shake on top.
+fn follow_targets(
+    time: Res<Time>,
+    settings: Res<Settings>,
+    mut shake: ResMut<CameraShake>,
+    players: Query<&GlobalTransform, With<Player>>,
+    cpu: Query<&GlobalTransform, With<Cpu>>,
+    enemies: Query<&GlobalTransform, With<Enemy>>,
+    mut camera: Single<&mut Transform, With<GameCamera>>,
+) {
+    let cpu = cpu
+        .single()
+        .map_or(Vec3::ZERO, GlobalTransform::translation);
+    let points: Vec<Vec3> = players
+        .iter()
+        .map(GlobalTransform::translation)
+        .chain(
+            enemies
+                .iter()
+                .map(GlobalTransform::translation)
+                .filter(|enemy| enemy.distance(cpu) < THREAT_RADIUS),
+        )
+        .chain([cpu])
+        .collect();
+
+    let center = points.iter().sum::<Vec3>() / points.len() as f32;
+    let spread = points
+        .iter()
+        .map(|point| point.with_y(0.).distance(center.with_y(0.)))
+        .fold(0., f32::max);
+    let height = (MIN_HEIGHT + spread * HEIGHT_PER_UNIT).min(MAX_HEIGHT);
+    let target = Vec3::new(center.x, height, center.z);
+
+    let blend = 1. - (-FOLLOW_SHARPNESS * time.delta_secs()).exp();
+    let position = (camera.translation - shake.offset).lerp(target, blend);
+
+    shake.trauma = (shake.trauma - TRAUMA_DECAY * time.delta_secs()).max(0.);
+    if !settings.screen_shake {
+        shake.trauma = 0.;
+    }
+    let mut rng = rand::rng();
+    let jitter = Vec3::new(rng.random_range(-1. ..=1.), rng.random_range(-1. ..=1.), 0.);
+    shake.offset = camera.rotation * jitter * shake.trauma.powi(2) * MAX_SHAKE_OFFSET;
+    camera.translation = position + shake.offset;
+}
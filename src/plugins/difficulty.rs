@@ -0,0 +1,89 @@
+use bevy::prelude::*;
+
+pub struct DifficultyPlugin;
+
+/// Preset chosen from the main menu, read by every system tuning enemies or the economy.
+#[derive(Resource, Default, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Difficulty {
+    Easy,
+    #[default]
+    Normal,
+    Hard,
+    Nightmare,
+}
+
+pub const DIFFICULTIES: &[Difficulty] = &[
+    Difficulty::Easy,
+    Difficulty::Normal,
+    Difficulty::Hard,
+    Difficulty::Nightmare,
+];
+
+/// Multipliers applied on top of the base gameplay constants.
+#[derive(Clone, Copy, Debug)]
+pub struct DifficultyPreset {
+    pub enemy_health: f32,
+    pub enemy_speed: f32,
+    /// Scales the delay between two spawns, lower means denser waves.
+    pub spawn_interval: f32,
+    pub coin_reward: f32,
+    /// Scales the CPU damage dealt by an enemy reaching it.
+    pub leak_damage: f32,
+}
+
+impl Difficulty {
+    /// Locale key of the difficulty's name.
+    pub fn key(self) -> &'static str {
+        match self {
+            Difficulty::Easy => "difficulty.easy",
+            Difficulty::Normal => "difficulty.normal",
+            Difficulty::Hard => "difficulty.hard",
+            Difficulty::Nightmare => "difficulty.nightmare",
+        }
+    }
+
+    pub fn preset(self) -> DifficultyPreset {
+        match self {
+            Difficulty::Easy => DifficultyPreset {
+                enemy_health: 0.7,
+                enemy_speed: 0.8,
+                spawn_interval: 1.3,
+                coin_reward: 1.5,
+                leak_damage: 0.5,
+            },
+            Difficulty::Normal => DifficultyPreset {
+                enemy_health: 1.,
+                enemy_speed: 1.,
+                spawn_interval: 1.,
+                coin_reward: 1.,
+                leak_damage: 1.,
+            },
+            Difficulty::Hard => DifficultyPreset {
+                enemy_health: 1.4,
+                enemy_speed: 1.2,
+                spawn_interval: 0.8,
+                coin_reward: 0.8,
+                leak_damage: 1.5,
+            },
+            Difficulty::Nightmare => DifficultyPreset {
+                enemy_health: 2.,
+                enemy_speed: 1.5,
+                spawn_interval: 0.6,
+                coin_reward: 0.6,
+                leak_damage: 2.5,
+            },
+        }
+    }
+
+    /// Preset following this one, wrapping around after the hardest.
+    pub fn next(self) -> Self {
+        let index = DIFFICULTIES.iter().position(|d| *d == self).unwrap_or(0);
+        DIFFICULTIES[(index + 1) % DIFFICULTIES.len()]
+    }
+}
+
+impl Plugin for DifficultyPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Difficulty>();
+    }
+}
@@ -0,0 +1,254 @@
+use std::time::Duration;
+
+use bevy::prelude::*;
+use rand::{
+    Rng, SeedableRng,
+    rngs::StdRng,
+    seq::{IndexedRandom, SliceRandom},
+};
+
+use crate::{
+    AppState,
+    plugins::enemy::{ENEMY_KINDS, EnemyKind, EnemySpawner},
+};
+
+pub struct EndlessPlugin;
+
+#[derive(Resource, Default, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum GameMode {
+    /// Hand-authored spawner counts, growing by a fixed amount per wave.
+    #[default]
+    Standard,
+    /// Waves generated from a seed, with a threat budget growing along a curve.
+    Endless,
+    /// A scripted first wave teaching the controls, then standard waves.
+    Tutorial,
+}
+
+impl GameMode {
+    /// Locale key of the mode's name.
+    pub fn key(self) -> &'static str {
+        match self {
+            GameMode::Standard => "mode.standard",
+            GameMode::Endless => "mode.endless",
+            GameMode::Tutorial => "mode.tutorial",
+        }
+    }
+
+    pub fn next(self) -> Self {
+        match self {
+            GameMode::Standard => GameMode::Endless,
+            GameMode::Endless => GameMode::Tutorial,
+            GameMode::Tutorial => GameMode::Standard,
+        }
+    }
+}
+
+/// Seed of the current endless run, the same seed always yields the same waves.
+#[derive(Resource, Default)]
+pub struct EndlessSeed(pub u64);
+
+/// A batch of enemies of one kind, sent down the same spawner.
+struct SpawnGroup {
+    kind: EnemyKind,
+    min: u32,
+    max: u32,
+}
+
+const SPAWN_GROUPS: &[SpawnGroup] = &[
+    SpawnGroup {
+        kind: EnemyKind::Grunt,
+        min: 3,
+        max: 6,
+    },
+    SpawnGroup {
+        kind: EnemyKind::Runner,
+        min: 4,
+        max: 8,
+    },
+    SpawnGroup {
+        kind: EnemyKind::Tank,
+        min: 1,
+        max: 2,
+    },
+];
+
+/// How far waypoints may drift sideways from the authored path.
+const PATH_JITTER: f32 = 0.75;
+
+impl EnemyKind {
+    /// Share of the threat budget one enemy of this kind costs.
+    fn threat(self) -> f32 {
+        match self {
+            EnemyKind::Grunt => 1.,
+            EnemyKind::Runner => 0.8,
+            EnemyKind::Tank => 3.5,
+        }
+    }
+
+    /// First endless wave this kind can appear in.
+    fn first_wave(self) -> u32 {
+        match self {
+            EnemyKind::Grunt => 1,
+            EnemyKind::Runner => 2,
+            EnemyKind::Tank => 4,
+        }
+    }
+}
+
+/// Threat points spent on wave `wave`, growing a bit faster than linearly.
+pub fn threat_budget(wave: u32) -> f32 {
+    6. + 4. * (wave as f32).powf(1.35)
+}
+
+/// Multiplier on spawner intervals, shrinking each wave down to a floor.
+pub fn spawn_interval_scale(wave: u32) -> f32 {
+    (1. / (1. + 0.08 * wave.saturating_sub(1) as f32)).max(0.35)
+}
+
+impl Plugin for EndlessPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<GameMode>()
+            .init_resource::<EndlessSeed>()
+            .add_systems(OnEnter(AppState::InGame), pick_seed);
+    }
+}
+
+fn pick_seed(mode: Res<GameMode>, mut seed: ResMut<EndlessSeed>) {
+    if *mode == GameMode::Endless {
+        seed.0 = rand::random();
+        info!("Endless seed: {}", seed.0);
+    }
+}
+
+/// Mirrors `path` across the spawner's axis half of the time and nudges every waypoint sideways.
+fn vary_path(spawner: &EnemySpawner, rng: &mut StdRng) -> Vec<Vec3> {
+    let sideways = if spawner.position.x.abs() < spawner.position.z.abs() {
+        Vec3::X
+    } else {
+        Vec3::Z
+    };
+    let mirror = rng.random_bool(0.5);
+    spawner
+        .path
+        .iter()
+        .map(|waypoint| {
+            let offset = waypoint.dot(sideways);
+            let offset = if mirror { -offset } else { offset };
+            let offset = offset + rng.random_range(-PATH_JITTER..=PATH_JITTER);
+            *waypoint - sideways * waypoint.dot(sideways) + sideways * offset
+        })
+        .collect()
+}
+
+/// Fills the spawners' queues for endless wave `wave`.
+pub fn plan_endless_wave(seed: u64, wave: u32, spawners: &mut [Mut<EnemySpawner>]) {
+    let mut rng = StdRng::seed_from_u64(seed ^ (wave as u64).wrapping_mul(0x9E37_79B9_7F4A_7C15));
+
+    // More entrances open up as waves go on.
+    let mut active: Vec<usize> = (0..spawners.len()).collect();
+    active.shuffle(&mut rng);
+    active.truncate((1 + wave as usize / 3).min(spawners.len()));
+    if active.is_empty() {
+        warn!("No enemy spawner to plan endless wave {wave} on");
+        return;
+    }
+
+    let interval_scale = spawn_interval_scale(wave);
+    for &index in &active {
+        let spawner = &mut spawners[index];
+        spawner.route = vary_path(spawner, &mut rng);
+        let interval = spawner.interval * interval_scale;
+        spawner
+            .timer
+            .set_duration(Duration::from_secs_f32(interval));
+    }
+
+    let cheapest = ENEMY_KINDS
+        .iter()
+        .filter(|kind| kind.first_wave() <= wave)
+        .map(|kind| kind.threat())
+        .fold(f32::INFINITY, f32::min);
+
+    let mut budget = threat_budget(wave);
+    while budget >= cheapest {
+        let affordable: Vec<&SpawnGroup> = SPAWN_GROUPS
+            .iter()
+            .filter(|group| group.kind.first_wave() <= wave && group.kind.threat() <= budget)
+            .collect();
+        let Some(group) = affordable.choose(&mut rng) else {
+            break;
+        };
+
+        let most = ((budget / group.kind.threat()) as u32).min(group.max);
+        let count = rng.random_range(group.min.min(most)..=most);
+        budget -= count as f32 * group.kind.threat();
+
+        let Some(&index) = active.choose(&mut rng) else {
+            break;
+        };
+        spawners[index]
+            .queue
+            .extend(std::iter::repeat_n(group.kind, count as usize));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::VecDeque;
+
+    use super::*;
+
+    /// Queue and route of each spawner once wave `wave` is planned.
+    fn plan(seed: u64, wave: u32, spawner_count: usize) -> Vec<(VecDeque<EnemyKind>, Vec<Vec3>)> {
+        let mut world = World::new();
+        for side in 0..spawner_count {
+            let position = Quat::from_rotation_y(side as f32 * std::f32::consts::FRAC_PI_2)
+                * Vec3::new(0., 0.5, -14.);
+            let path = vec![position * 0.2, position * 0.5, position * 0.8];
+            world.spawn(EnemySpawner {
+                position,
+                timer: Timer::from_seconds(2., TimerMode::Repeating),
+                interval: 2.,
+                route: path.clone(),
+                path,
+                base_count: 5,
+                count_growth: 2,
+                queue: VecDeque::new(),
+            });
+        }
+
+        let mut spawners = world.query::<&mut EnemySpawner>();
+        let mut spawners: Vec<Mut<EnemySpawner>> = spawners.iter_mut(&mut world).collect();
+        plan_endless_wave(seed, wave, &mut spawners);
+        spawners
+            .iter()
+            .map(|spawner| (spawner.queue.clone(), spawner.route.clone()))
+            .collect()
+    }
+
+    #[test]
+    fn same_seed_and_wave_plan_the_same_wave_within_budget() {
+        for wave in 1..=20 {
+            let planned = plan(42, wave, 4);
+            assert_eq!(planned, plan(42, wave, 4), "wave {wave}");
+
+            let threat: f32 = planned
+                .iter()
+                .flat_map(|(queue, _)| queue)
+                .map(|kind| kind.threat())
+                .sum();
+            assert!(threat > 0., "wave {wave} is empty");
+            assert!(
+                threat <= threat_budget(wave) + 1e-3,
+                "wave {wave} costs {threat}, over its budget of {}",
+                threat_budget(wave)
+            );
+        }
+    }
+
+    #[test]
+    fn planning_without_spawners_does_nothing() {
+        assert!(plan(42, 5, 0).is_empty());
+    }
+}
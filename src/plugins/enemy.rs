@@ -1,56 +1,246 @@
-use std::process::exit;
+use std::collections::{HashMap, VecDeque};
 
 use avian3d::prelude::{Collider, CollisionEventsEnabled, CollisionStart, Sensor};
-use bevy::prelude::*;
+use bevy::{ecs::system::SystemParam, prelude::*};
 
 use crate::{
-    Cpu, DespawnOnFinish,
-    plugins::{gun::Bullet, health::Health, player::Player, ui::CpuHealthBar},
+    AppState, Cpu,
+    plugins::{
+        accessibility::Swatch,
+        difficulty::Difficulty,
+        gun::Bullet,
+        health::Health,
+        level::Level,
+        model::{Model, ModelKind, Tint},
+        net::SimulationSystems,
+        particles::{Effect, EmitEffect},
+        player::Player,
+        powerup::{ActiveBuffs, PowerUpKind},
+        settings::Settings,
+        sound::{PlaySfx, Sfx},
+        stats::RunStats,
+        upgrade::Stat,
+        wave::GamePhase,
+    },
 };
 
 #[derive(Component)]
 pub struct Enemy;
 
+#[derive(Component, Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum EnemyKind {
+    Grunt,
+    /// Fast and fragile, comes in packs.
+    Runner,
+    /// Slow and heavily armored, worth a lot of coins.
+    Tank,
+}
+
+pub const ENEMY_KINDS: &[EnemyKind] = &[EnemyKind::Grunt, EnemyKind::Runner, EnemyKind::Tank];
+
+impl EnemyKind {
+    /// Locale key of the kind's name.
+    pub fn key(self) -> &'static str {
+        match self {
+            EnemyKind::Grunt => "enemy.grunt",
+            EnemyKind::Runner => "enemy.runner",
+            EnemyKind::Tank => "enemy.tank",
+        }
+    }
+
+    /// Multiplier on [`ENEMY_HEALTH`].
+    fn health_scale(self) -> f32 {
+        match self {
+            EnemyKind::Grunt => 1.,
+            EnemyKind::Runner => 0.5,
+            EnemyKind::Tank => 3.,
+        }
+    }
+
+    /// Multiplier on [`ENEMY_SPEED`].
+    fn speed_scale(self) -> f32 {
+        match self {
+            EnemyKind::Grunt => 1.,
+            EnemyKind::Runner => 1.8,
+            EnemyKind::Tank => 0.6,
+        }
+    }
+
+    /// Multiplier on [`COINS_PER_KILL`].
+    fn coin_scale(self) -> f32 {
+        match self {
+            EnemyKind::Grunt => 1.,
+            EnemyKind::Runner => 0.8,
+            EnemyKind::Tank => 3.,
+        }
+    }
+
+    pub fn size(self) -> f32 {
+        match self {
+            EnemyKind::Grunt => 1.,
+            EnemyKind::Runner => 0.7,
+            EnemyKind::Tank => 1.4,
+        }
+    }
+}
+
 pub struct EnemyPlugin;
 
 #[derive(Component, Deref, DerefMut)]
 pub struct EnemyMovement(pub Vec<Vec3>);
 
+/// What brought an enemy's health to zero.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum KillCause {
+    Bullet {
+        shooter: Entity,
+    },
+    /// A bomb, collected by `player`.
+    Bomb {
+        player: Entity,
+    },
+}
+
+/// Sent when an enemy's health is brought to zero, by a bullet or a bomb.
+#[derive(Message)]
+pub struct EnemyKilled {
+    pub kind: EnemyKind,
+    pub position: Vec3,
+    /// Coins dropped where the enemy fell.
+    pub coins: u32,
+    pub cause: KillCause,
+}
+
 #[derive(Resource)]
-pub struct EnemyDieAudio(pub Handle<AudioSource>);
+pub struct EnemyModel {
+    pub meshes: HashMap<EnemyKind, Handle<Mesh>>,
+    pub materials: HashMap<EnemyKind, Handle<StandardMaterial>>,
+}
 
 #[derive(Component)]
 pub struct EnemySpawner {
     pub position: Vec3,
     pub timer: Timer,
+    /// Delay between two spawns, before any per-wave ramp.
+    pub interval: f32,
+    /// Waypoints toward the CPU, last one first.
     pub path: Vec<Vec3>,
+    /// Waypoints used by the current wave, a variation of `path` in endless mode.
+    pub route: Vec<Vec3>,
+    /// Enemies spawned during the first wave.
+    pub base_count: u32,
+    /// Extra enemies spawned per wave after the first one.
+    pub count_growth: u32,
+    /// Enemies left to spawn in the current wave, in spawn order.
+    pub queue: VecDeque<EnemyKind>,
+}
+
+impl EnemySpawner {
+    pub fn wave_size(&self, wave: u32) -> u32 {
+        self.base_count + self.count_growth * wave.saturating_sub(1)
+    }
 }
 
 impl Plugin for EnemyPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(Startup, (spawn_enemy_spawner, spawn_enemy_die_audio))
-            .add_systems(Update, (move_enemy, handle_enemy_hit, handle_enemy_spawn));
+        app.add_message::<EnemyKilled>()
+            .add_systems(Startup, insert_enemy_model)
+            .add_systems(
+                Update,
+                (
+                    recolor_enemy_model.run_if(resource_changed::<Settings>),
+                    tint_enemies,
+                ),
+            )
+            .add_systems(OnEnter(AppState::InGame), spawn_enemy_spawner)
+            .add_systems(
+                Update,
+                (move_enemy, handle_enemy_hit).in_set(SimulationSystems),
+            )
+            .add_systems(
+                Update,
+                handle_enemy_spawn
+                    .in_set(SimulationSystems)
+                    .run_if(in_state(GamePhase::Combat)),
+            );
     }
 }
 
-fn spawn_enemy_die_audio(asset_server: Res<AssetServer>, mut commands: Commands) {
-    commands.insert_resource(EnemyDieAudio(asset_server.load("die.ogg")));
+fn insert_enemy_model(
+    mut commands: Commands,
+    settings: Res<Settings>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    commands.insert_resource(EnemyModel {
+        meshes: ENEMY_KINDS
+            .iter()
+            .map(|kind| (*kind, meshes.add(Cuboid::from_length(kind.size()))))
+            .collect(),
+        materials: ENEMY_KINDS
+            .iter()
+            .map(|kind| {
+                let color = settings.palette.color(Swatch::Enemy(*kind));
+                (*kind, materials.add(color))
+            })
+            .collect(),
+    });
+}
+
+fn recolor_enemy_model(
+    settings: Res<Settings>,
+    model: Res<EnemyModel>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    for (kind, handle) in &model.materials {
+        if let Some(material) = materials.get_mut(handle) {
+            material.base_color = settings.palette.color(Swatch::Enemy(*kind));
+        }
+    }
+}
+
+/// Colors enemy models, replicas included, like the placeholder of their kind.
+fn tint_enemies(settings: Res<Settings>, mut enemies: Query<(Ref<EnemyKind>, &mut Tint)>) {
+    for (kind, mut tint) in &mut enemies {
+        if settings.is_changed() || kind.is_added() {
+            tint.0 = settings.palette.color(Swatch::Enemy(*kind));
+        }
+    }
 }
 
 const ENEMY_SPEED: f32 = 2.;
+const ENEMY_HEALTH: f32 = 100.;
+const COINS_PER_KILL: u32 = 5;
+/// CPU health lost when an enemy reaches it.
+const LEAK_DAMAGE: f32 = 10.;
 
-const BASE_BULLET_DAMAGE: f32 = 10.;
-const DAMAGE_PER_LEVEL: f32 = 5.;
+/// Where the outcome of a hit is reported.
+#[derive(SystemParam)]
+struct HitReports<'w> {
+    kills: MessageWriter<'w, EnemyKilled>,
+    sfx: MessageWriter<'w, PlaySfx>,
+    effects: MessageWriter<'w, EmitEffect>,
+    stats: ResMut<'w, RunStats>,
+}
 
 fn handle_enemy_hit(
     mut collision_start_event_reader: MessageReader<CollisionStart>,
-    bullet_query: Query<Entity, With<Bullet>>,
-    mut enemy_query: Query<&mut Health, With<Enemy>>,
+    bullet_query: Query<&Bullet>,
+    mut enemy_query: Query<(&mut Health, &Transform, &EnemyKind), With<Enemy>>,
+    mut players: Query<(&mut Player, &ActiveBuffs)>,
     mut commands: Commands,
-    mut player: Single<&mut Player>,
-    audio: Res<EnemyDieAudio>,
+    difficulty: Res<Difficulty>,
+    mut reports: HitReports,
 ) {
-    let damage = BASE_BULLET_DAMAGE + player.damage_level as f32 * DAMAGE_PER_LEVEL;
+    let HitReports {
+        kills,
+        sfx,
+        effects,
+        stats,
+    } = &mut reports;
+    let coin_reward = COINS_PER_KILL as f32 * difficulty.preset().coin_reward;
+    // A bullet overlapping two enemies at once reports both, only the first one takes it.
+    let mut spent = Vec::new();
 
     for CollisionStart {
         collider1,
@@ -59,34 +249,75 @@ fn handle_enemy_hit(
         body2: _,
     } in collision_start_event_reader.read()
     {
-        let target = if bullet_query.contains(collider1.entity()) {
-            commands.entity(collider1.entity()).despawn();
-            collider2
+        let (bullet_entity, target) = if bullet_query.contains(collider1.entity()) {
+            (collider1.entity(), collider2.entity())
         } else if bullet_query.contains(collider2.entity()) {
-            commands.entity(collider2.entity()).despawn();
-            collider1
+            (collider2.entity(), collider1.entity())
         } else {
             continue;
         };
+        // Bullets are sensors and also overlap players and pickups, only enemies stop them.
+        let Ok((mut health, transform, kind)) = enemy_query.get_mut(target) else {
+            continue;
+        };
+        if spent.contains(&bullet_entity) {
+            continue;
+        }
+        spent.push(bullet_entity);
+        commands.entity(bullet_entity).try_despawn();
 
-        if let Ok(mut health) = enemy_query.get_mut(target.entity()) {
+        let Ok(bullet) = bullet_query.get(bullet_entity) else {
+            continue;
+        };
+        let Ok((mut shooter, buffs)) = players.get_mut(bullet.owner) else {
+            continue;
+        };
+        let damage = shooter.upgrades.stat(Stat::Damage) * buffs.stat_multiplier(Stat::Damage);
+
+        if health.0 > 0. {
+            stats.hits += 1;
+            stats.damage_dealt += damage.min(health.0);
             health.0 -= damage;
+            effects.write(EmitEffect {
+                effect: Effect::Spark,
+                position: transform.translation,
+                direction: None,
+            });
             if health.0 <= 0. {
-                player.coins += 5;
-                commands.spawn((AudioPlayer::new(audio.0.clone()), DespawnOnFinish));
+                shooter.kills += 1;
+                kills.write(EnemyKilled {
+                    kind: *kind,
+                    position: transform.translation,
+                    coins: ((coin_reward * kind.coin_scale()).round() as u32).max(1),
+                    cause: KillCause::Bullet {
+                        shooter: bullet.owner,
+                    },
+                });
+                sfx.write(PlaySfx(Sfx::EnemyDie));
             }
         }
     }
 }
 
+type CpuTarget<'w, 's> =
+    Single<'w, 's, (&'static mut Health, &'static Transform), (With<Cpu>, Without<Enemy>)>;
+
 fn move_enemy(
-    query: Query<(Entity, &mut Transform, &mut EnemyMovement), With<Enemy>>,
+    query: Query<(Entity, &mut Transform, &mut EnemyMovement, &EnemyKind), With<Enemy>>,
     mut commands: Commands,
-    mut cpu: Single<&mut Health, With<Cpu>>,
+    cpu: CpuTarget,
     time: Res<Time>,
-    mut health_bar: Single<&mut Node, With<CpuHealthBar>>,
+    buffs: Query<&ActiveBuffs>,
+    mut next_state: ResMut<NextState<AppState>>,
+    difficulty: Res<Difficulty>,
 ) {
-    for (entity, mut transform, mut movement) in query {
+    let preset = difficulty.preset();
+    let shielded = buffs.iter().any(|buffs| buffs.has(PowerUpKind::Shield));
+    let (mut cpu, cpu_transform) = cpu.into_inner();
+    let cpu_position = cpu_transform.translation;
+
+    for (entity, mut transform, mut movement, kind) in query {
+        let speed = ENEMY_SPEED * preset.enemy_speed * kind.speed_scale();
         if let Some(target) = movement.last().cloned() {
             let distance = transform.translation.distance(target);
 
@@ -96,87 +327,82 @@ fn move_enemy(
 
             let direction = (target - transform.translation).normalize();
 
-            transform.translation += direction * ENEMY_SPEED * time.delta_secs()
+            transform.translation += direction * speed * time.delta_secs()
         } else {
-            let direction = (Vec3::new(0., 0.5, 0.) - transform.translation).normalize();
+            let direction = (cpu_position - transform.translation).normalize();
 
-            transform.translation += direction * ENEMY_SPEED * time.delta_secs();
+            transform.translation += direction * speed * time.delta_secs();
 
-            if transform.translation.distance(Vec3::new(0., 0.5, 0.)) <= 0.2 {
+            if transform.translation.distance(cpu_position) <= 0.2 {
                 commands.entity(entity).despawn();
-                cpu.0 -= 10.;
-                health_bar.width = percent(cpu.0);
+                if shielded {
+                    continue;
+                }
+                cpu.0 -= LEAK_DAMAGE * preset.leak_damage;
                 if cpu.0 <= 0. {
-                    exit(0);
+                    next_state.set(AppState::GameOver);
                 }
             }
         }
     }
 }
 
-fn spawn_enemy_spawner(mut commands: Commands) {
-    commands.spawn(EnemySpawner {
-        timer: Timer::from_seconds(3., TimerMode::Repeating),
-        position: Vec3::new(0., 0.5, -14.),
-        path: vec![
-            Vec3::new(-1., 0.5, -2.),
-            Vec3::new(1.5, 0.5, -5.),
-            Vec3::new(-2., 0.5, -9.),
-        ],
-    });
-
-    commands.spawn(EnemySpawner {
-        timer: Timer::from_seconds(12., TimerMode::Repeating),
-        position: Vec3::new(0., 0.5, 14.),
-        path: vec![
-            Vec3::new(1., 0.5, 2.),
-            Vec3::new(-1.5, 0.5, 5.),
-            Vec3::new(2., 0.5, 9.),
-        ],
-    });
-
-    commands.spawn(EnemySpawner {
-        timer: Timer::from_seconds(17., TimerMode::Repeating),
-        position: Vec3::new(14., 0.5, 0.),
-        path: vec![
-            Vec3::new(2., 0.5, 1.),
-            Vec3::new(6., 0.5, -1.5),
-            Vec3::new(10., 0.5, 1.),
-        ],
-    });
+/// Delay between spawns, first-wave count and per-wave growth of each spawner, in level order.
+///
+/// Levels with more spawners reuse the last entry.
+const SPAWNER_PACING: &[(f32, u32, u32)] = &[(3., 6, 2), (12., 2, 1), (17., 1, 1), (25., 1, 0)];
 
-    commands.spawn(EnemySpawner {
-        timer: Timer::from_seconds(25., TimerMode::Repeating),
-        position: Vec3::new(-14., 0.5, 0.),
-        path: vec![
-            Vec3::new(-2., 0.5, -1.),
-            Vec3::new(-6., 0.5, 1.5),
-            Vec3::new(-10., 0.5, -1.),
-        ],
-    });
+fn spawn_enemy_spawner(mut commands: Commands, difficulty: Res<Difficulty>, level: Res<Level>) {
+    let spawn_interval = difficulty.preset().spawn_interval;
+    for (index, layout) in level.spawners.iter().enumerate() {
+        let (interval, base_count, count_growth) =
+            SPAWNER_PACING[index.min(SPAWNER_PACING.len() - 1)];
+        let interval = interval * spawn_interval;
+        commands.spawn((
+            DespawnOnExit(AppState::InGame),
+            EnemySpawner {
+                position: layout.position,
+                timer: Timer::from_seconds(interval, TimerMode::Repeating),
+                interval,
+                route: layout.path.clone(),
+                path: layout.path.clone(),
+                base_count,
+                count_growth,
+                queue: VecDeque::new(),
+            },
+        ));
+    }
 }
 
 fn handle_enemy_spawn(
     mut commands: Commands,
-    mut meshes: ResMut<Assets<Mesh>>,
-    mut materials: ResMut<Assets<StandardMaterial>>,
+    model: Res<EnemyModel>,
     mut query: Query<&mut EnemySpawner>,
     time: Res<Time>,
+    difficulty: Res<Difficulty>,
 ) {
+    let health = ENEMY_HEALTH * difficulty.preset().enemy_health;
     let delta = time.delta();
     for mut spawner in &mut query {
         spawner.timer.tick(delta);
 
-        if spawner.timer.just_finished() {
+        if !spawner.timer.just_finished() {
+            continue;
+        }
+        if let Some(kind) = spawner.queue.pop_front() {
+            let size = kind.size();
             commands.spawn((
+                DespawnOnExit(AppState::InGame),
                 Enemy,
-                Health(100.),
-                EnemyMovement(spawner.path.clone()),
-                Mesh3d(meshes.add(Cuboid::new(1.0, 1.0, 1.0))),
-                MeshMaterial3d(materials.add(Color::srgb_u8(255, 50, 50))),
-                Transform::from_xyz(spawner.position.x, spawner.position.y, spawner.position.z),
+                kind,
+                Health(health * kind.health_scale()),
+                EnemyMovement(spawner.route.clone()),
+                Mesh3d(model.meshes[&kind].clone()),
+                MeshMaterial3d(model.materials[&kind].clone()),
+                Model::new(ModelKind::Cockroach).scaled(size),
+                Transform::from_translation(spawner.position),
                 Sensor::default(),
-                Collider::cuboid(1.0, 1.0, 1.0),
+                Collider::cuboid(size, size, size),
                 CollisionEventsEnabled,
             ));
             spawner.timer.reset();
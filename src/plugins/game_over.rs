@@ -0,0 +1,390 @@
+use bevy::{ecs::system::SystemParam, prelude::*};
+
+use crate::{
+    AppState,
+    plugins::{
+        enemy::ENEMY_KINDS,
+        highscore::{HighScore, HighScores, INITIALS_LEN},
+        locale::{Locale, Localized},
+        menu::{CapturesDpad, MENU_ITEM_COLOR, MenuActivated, MenuItem},
+        stats::RunStats,
+    },
+};
+
+pub struct GameOverPlugin;
+
+#[derive(Component)]
+enum GameOverAction {
+    SaveScore,
+    Retry,
+    MainMenu,
+}
+
+/// Holds either the initials entry or, once saved, the ways out of the screen.
+#[derive(Component)]
+struct GameOverContent;
+
+/// Letters being picked with the D-pad, which the menu leaves alone meanwhile.
+#[derive(Component)]
+#[require(CapturesDpad)]
+struct InitialsEntry {
+    letters: [u8; INITIALS_LEN],
+    cursor: usize,
+}
+
+#[derive(Component)]
+#[require(Button)]
+struct InitialSlot(usize);
+
+const SLOT_COLOR: Color = Color::srgb_u8(50, 50, 50);
+const SLOT_FOCUSED_COLOR: Color = Color::srgb_u8(90, 90, 160);
+
+impl Plugin for GameOverPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnEnter(AppState::GameOver), spawn_game_over)
+            .add_systems(
+                Update,
+                (
+                    edit_initials,
+                    update_initial_slots,
+                    handle_game_over_actions,
+                )
+                    .chain()
+                    .run_if(in_state(AppState::GameOver)),
+            );
+    }
+}
+
+fn format_time(seconds: f32) -> String {
+    let seconds = seconds as u32;
+    format!("{}:{:02}", seconds / 60, seconds % 60)
+}
+
+fn game_over_button(order: usize, action: GameOverAction, key: &'static str) -> impl Bundle {
+    (
+        MenuItem(order),
+        action,
+        Node {
+            width: px(260),
+            padding: UiRect::axes(px(16), px(10)),
+            margin: UiRect::top(px(12)),
+            justify_content: JustifyContent::Center,
+            ..default()
+        },
+        BackgroundColor(MENU_ITEM_COLOR),
+        children![(
+            Localized::new(key),
+            TextFont {
+                font_size: 18.,
+                ..default()
+            },
+        )],
+    )
+}
+
+/// Retry and main menu, offered once there is no score left to save.
+fn spawn_exit_buttons(content: &mut ChildSpawnerCommands) {
+    content.spawn(game_over_button(
+        0,
+        GameOverAction::Retry,
+        "game_over.retry",
+    ));
+    content.spawn(game_over_button(
+        1,
+        GameOverAction::MainMenu,
+        "game_over.main_menu",
+    ));
+}
+
+fn initial_slot(index: usize) -> impl Bundle {
+    (
+        InitialSlot(index),
+        Node {
+            width: px(48),
+            height: px(56),
+            margin: UiRect::horizontal(px(4)),
+            justify_content: JustifyContent::Center,
+            align_items: AlignItems::Center,
+            ..default()
+        },
+        BackgroundColor(SLOT_COLOR),
+        children![(
+            Text::new("A"),
+            TextFont {
+                font_size: 36.,
+                ..default()
+            },
+        )],
+    )
+}
+
+/// The language cannot change on this screen, so its lines are written once.
+fn spawn_game_over(
+    mut commands: Commands,
+    stats: Res<RunStats>,
+    high_scores: Res<HighScores>,
+    locale: Res<Locale>,
+) {
+    let score = stats.score();
+    let kills_by_kind: Vec<_> = ENEMY_KINDS
+        .iter()
+        .filter_map(|kind| {
+            let kills = stats.kills_by_kind.get(kind).copied().unwrap_or_default();
+            (kills > 0).then(|| {
+                locale.format(
+                    "game_over.kind_kills",
+                    &[("kind", &locale.get(kind.key())), ("count", &kills)],
+                )
+            })
+        })
+        .collect();
+    let kills = if kills_by_kind.is_empty() {
+        stats.kills.to_string()
+    } else {
+        format!("{} ({})", stats.kills, kills_by_kind.join(", "))
+    };
+    let accuracy = stats.accuracy().map_or("-".to_string(), |accuracy| {
+        format!("{:.0}%", accuracy * 100.)
+    });
+    let lines = [
+        locale.format(
+            "game_over.time_alive",
+            &[("time", &format_time(stats.time_alive))],
+        ),
+        locale.format(
+            "game_over.waves_cleared",
+            &[("count", &stats.waves_cleared)],
+        ),
+        locale.format("game_over.kills", &[("kills", &kills)]),
+        locale.format(
+            "game_over.shots",
+            &[("shots", &stats.shots_fired), ("accuracy", &accuracy)],
+        ),
+        locale.format(
+            "game_over.damage",
+            &[("damage", &format!("{:.0}", stats.damage_dealt))],
+        ),
+        locale.format(
+            "game_over.coins",
+            &[
+                ("earned", &stats.coins_earned),
+                ("spent", &stats.coins_spent),
+            ],
+        ),
+        locale.format("game_over.upgrades", &[("count", &stats.upgrades_bought)]),
+    ]
+    .map(|line| {
+        (
+            Text::new(line),
+            TextFont {
+                font_size: 18.,
+                ..default()
+            },
+        )
+    });
+
+    let content = commands
+        .spawn((
+            GameOverContent,
+            Node {
+                display: Display::Flex,
+                flex_direction: FlexDirection::Column,
+                align_items: AlignItems::Center,
+                margin: UiRect::top(px(16)),
+                ..default()
+            },
+        ))
+        .id();
+
+    if high_scores.qualifies(score) {
+        commands.entity(content).with_children(|content| {
+            content.spawn((
+                Localized::new("game_over.new_high_score"),
+                TextFont {
+                    font_size: 16.,
+                    ..default()
+                },
+            ));
+            content.spawn((
+                InitialsEntry {
+                    letters: [b'A'; INITIALS_LEN],
+                    cursor: 0,
+                },
+                Node {
+                    display: Display::Flex,
+                    margin: UiRect::top(px(8)),
+                    ..default()
+                },
+                Children::spawn(SpawnIter((0..INITIALS_LEN).map(initial_slot))),
+            ));
+            content.spawn(game_over_button(
+                0,
+                GameOverAction::SaveScore,
+                "game_over.save_score",
+            ));
+        });
+    } else {
+        commands.entity(content).with_children(spawn_exit_buttons);
+    }
+
+    commands
+        .spawn((
+            DespawnOnExit(AppState::GameOver),
+            Node {
+                position_type: PositionType::Absolute,
+                width: percent(100),
+                height: percent(100),
+                display: Display::Flex,
+                flex_direction: FlexDirection::Column,
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                ..default()
+            },
+            BackgroundColor(Color::srgba_u8(0, 0, 0, 220)),
+            children![
+                (
+                    Localized::new("game_over.title"),
+                    TextFont {
+                        font_size: 40.,
+                        ..default()
+                    },
+                    TextColor(Color::srgb_u8(255, 80, 80)),
+                    Node {
+                        margin: UiRect::bottom(px(16)),
+                        ..default()
+                    },
+                ),
+                (
+                    Node {
+                        display: Display::Flex,
+                        flex_direction: FlexDirection::Column,
+                        align_items: AlignItems::Center,
+                        ..default()
+                    },
+                    Children::spawn(SpawnIter(lines.into_iter())),
+                ),
+                (
+                    Localized::new("game_over.score").with("score", score),
+                    TextFont {
+                        font_size: 28.,
+                        ..default()
+                    },
+                    Node {
+                        margin: UiRect::top(px(12)),
+                        ..default()
+                    },
+                )
+            ],
+        ))
+        .add_child(content);
+}
+
+fn cycle_letter(letter: u8, step: i8) -> u8 {
+    b'A' + ((letter - b'A') as i8 + step).rem_euclid(26) as u8
+}
+
+/// D-pad up/down changes the selected letter, left/right moves between letters; clicking cycles one.
+fn edit_initials(
+    gamepads: Query<&Gamepad>,
+    slots: Query<(&InitialSlot, &Interaction), Changed<Interaction>>,
+    mut entry: Single<&mut InitialsEntry>,
+) {
+    for gamepad in &gamepads {
+        let cursor = entry.cursor;
+        if gamepad.just_pressed(GamepadButton::DPadUp) {
+            entry.letters[cursor] = cycle_letter(entry.letters[cursor], 1);
+        }
+        if gamepad.just_pressed(GamepadButton::DPadDown) {
+            entry.letters[cursor] = cycle_letter(entry.letters[cursor], -1);
+        }
+        if gamepad.just_pressed(GamepadButton::DPadRight) {
+            entry.cursor = (cursor + 1) % INITIALS_LEN;
+        }
+        if gamepad.just_pressed(GamepadButton::DPadLeft) {
+            entry.cursor = (cursor + INITIALS_LEN - 1) % INITIALS_LEN;
+        }
+    }
+
+    for (InitialSlot(index), interaction) in &slots {
+        if *interaction == Interaction::Pressed {
+            entry.cursor = *index;
+            entry.letters[*index] = cycle_letter(entry.letters[*index], 1);
+        }
+    }
+}
+
+fn update_initial_slots(
+    entry: Single<Ref<InitialsEntry>>,
+    mut slots: Query<(&InitialSlot, &mut BackgroundColor, &Children)>,
+    mut texts: Query<&mut Text>,
+) {
+    if !entry.is_changed() {
+        return;
+    }
+    for (InitialSlot(index), mut color, children) in &mut slots {
+        color.0 = if *index == entry.cursor {
+            SLOT_FOCUSED_COLOR
+        } else {
+            SLOT_COLOR
+        };
+        if let Some(mut text) = children
+            .first()
+            .and_then(|child| texts.get_mut(*child).ok())
+        {
+            text.0 = (entry.letters[*index] as char).to_string();
+        }
+    }
+}
+
+/// The run's stats and the table they are saved to.
+#[derive(SystemParam)]
+struct Scores<'w> {
+    stats: Res<'w, RunStats>,
+    high_scores: ResMut<'w, HighScores>,
+}
+
+fn handle_game_over_actions(
+    mut activations: MessageReader<MenuActivated>,
+    actions: Query<&GameOverAction>,
+    entry: Query<&InitialsEntry>,
+    content: Single<Entity, With<GameOverContent>>,
+    mut scores: Scores,
+    mut next_state: ResMut<NextState<AppState>>,
+    mut commands: Commands,
+) {
+    for activation in activations.read() {
+        match actions.get(activation.item) {
+            Ok(GameOverAction::SaveScore) => {
+                let Ok(entry) = entry.single() else {
+                    continue;
+                };
+                let initials = String::from_utf8_lossy(&entry.letters).into_owned();
+                let rank = scores
+                    .high_scores
+                    .insert(HighScore::new(initials, &scores.stats));
+                scores.high_scores.save();
+
+                let message = match rank {
+                    Some(rank) => Localized::new("game_over.saved").with("rank", rank),
+                    None => Localized::new("game_over.not_saved"),
+                };
+                commands
+                    .entity(*content)
+                    .despawn_related::<Children>()
+                    .with_children(|content| {
+                        content.spawn((
+                            message,
+                            TextFont {
+                                font_size: 20.,
+                                ..default()
+                            },
+                        ));
+                        spawn_exit_buttons(content);
+                    });
+            }
+            Ok(GameOverAction::Retry) => next_state.set(AppState::InGame),
+            Ok(GameOverAction::MainMenu) => next_state.set(AppState::MainMenu),
+            Err(_) => {}
+        }
+    }
+}
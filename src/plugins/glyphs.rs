@@ -0,0 +1,215 @@
+//! Button prompts drawn for the device the players last touched.
+//!
+//! Prompts name a [`Glyph`] rather than a picture, and [`GlyphIcon`] nodes are redrawn whenever
+//! [`LastDevice`] switches to another [`GlyphSet`].
+
+use bevy::{
+    input::{
+        InputSystems,
+        gamepad::{GamepadConnection, GamepadConnectionEvent},
+    },
+    prelude::*,
+    ui::UiSystems,
+};
+
+pub struct GlyphPlugin;
+
+#[derive(Default, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum GlyphSet {
+    /// Also used for gamepads of unknown make.
+    #[default]
+    Xbox,
+    PlayStation,
+    Nintendo,
+    Keyboard,
+}
+
+const SONY_VENDOR_ID: u16 = 0x054C;
+const NINTENDO_VENDOR_ID: u16 = 0x057E;
+
+impl GlyphSet {
+    fn for_vendor(vendor_id: Option<u16>) -> Self {
+        match vendor_id {
+            Some(SONY_VENDOR_ID) => GlyphSet::PlayStation,
+            Some(NINTENDO_VENDOR_ID) => GlyphSet::Nintendo,
+            _ => GlyphSet::Xbox,
+        }
+    }
+}
+
+/// Glyph set of the device that was used last, the keyboard then plays for player 1.
+#[derive(Resource, Default, PartialEq, Eq, Debug)]
+pub struct LastDevice(pub GlyphSet);
+
+/// Control shown in a prompt.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Glyph {
+    Button(GamepadButton),
+    LeftStick,
+    RightStick,
+}
+
+/// Key standing in for `button` on the keyboard, face buttons are laid out as I, J, K and L.
+pub fn keyboard_key(button: GamepadButton) -> Option<KeyCode> {
+    match button {
+        GamepadButton::South => Some(KeyCode::KeyK),
+        GamepadButton::East => Some(KeyCode::KeyL),
+        GamepadButton::West => Some(KeyCode::KeyJ),
+        GamepadButton::North => Some(KeyCode::KeyI),
+        GamepadButton::RightTrigger2 => Some(KeyCode::Space),
+        _ => None,
+    }
+}
+
+impl Glyph {
+    pub fn label(self, set: GlyphSet) -> &'static str {
+        match (set, self) {
+            (GlyphSet::Keyboard, Glyph::LeftStick) => "WASD",
+            (GlyphSet::Keyboard, Glyph::RightStick) => "Arrows",
+            (GlyphSet::Keyboard, Glyph::Button(button)) => match keyboard_key(button) {
+                Some(KeyCode::KeyK) => "K",
+                Some(KeyCode::KeyL) => "L",
+                Some(KeyCode::KeyJ) => "J",
+                Some(KeyCode::KeyI) => "I",
+                Some(KeyCode::Space) => "Space",
+                _ => "?",
+            },
+            (_, Glyph::LeftStick) => "LS",
+            (_, Glyph::RightStick) => "RS",
+
+            (GlyphSet::Xbox, Glyph::Button(GamepadButton::South)) => "A",
+            (GlyphSet::Xbox, Glyph::Button(GamepadButton::East)) => "B",
+            (GlyphSet::Xbox, Glyph::Button(GamepadButton::West)) => "X",
+            (GlyphSet::Xbox, Glyph::Button(GamepadButton::North)) => "Y",
+            (GlyphSet::Xbox, Glyph::Button(GamepadButton::RightTrigger2)) => "RT",
+
+            (GlyphSet::PlayStation, Glyph::Button(GamepadButton::South)) => "Cross",
+            (GlyphSet::PlayStation, Glyph::Button(GamepadButton::East)) => "Circle",
+            (GlyphSet::PlayStation, Glyph::Button(GamepadButton::West)) => "Square",
+            (GlyphSet::PlayStation, Glyph::Button(GamepadButton::North)) => "Triangle",
+            (GlyphSet::PlayStation, Glyph::Button(GamepadButton::RightTrigger2)) => "R2",
+
+            (GlyphSet::Nintendo, Glyph::Button(GamepadButton::South)) => "B",
+            (GlyphSet::Nintendo, Glyph::Button(GamepadButton::East)) => "A",
+            (GlyphSet::Nintendo, Glyph::Button(GamepadButton::West)) => "Y",
+            (GlyphSet::Nintendo, Glyph::Button(GamepadButton::North)) => "X",
+            (GlyphSet::Nintendo, Glyph::Button(GamepadButton::RightTrigger2)) => "ZR",
+
+            (_, Glyph::Button(_)) => "?",
+        }
+    }
+
+    /// Picture of the glyph, if there is one, otherwise its label is drawn on a badge.
+    fn image(self, set: GlyphSet) -> Option<&'static str> {
+        let Glyph::Button(button) = self else {
+            return None;
+        };
+        match (set, button) {
+            (GlyphSet::Xbox, GamepadButton::South) | (GlyphSet::Nintendo, GamepadButton::East) => {
+                Some("A.png")
+            }
+            (GlyphSet::Xbox, GamepadButton::East) | (GlyphSet::Nintendo, GamepadButton::South) => {
+                Some("B.png")
+            }
+            (GlyphSet::Xbox, GamepadButton::West) | (GlyphSet::Nintendo, GamepadButton::North) => {
+                Some("X.png")
+            }
+            (GlyphSet::Xbox, GamepadButton::North) | (GlyphSet::Nintendo, GamepadButton::West) => {
+                Some("Y.png")
+            }
+            (GlyphSet::PlayStation, GamepadButton::South) => Some("Cross.png"),
+            (GlyphSet::PlayStation, GamepadButton::East) => Some("Circle.png"),
+            (GlyphSet::PlayStation, GamepadButton::West) => Some("Square.png"),
+            (GlyphSet::PlayStation, GamepadButton::North) => Some("Triangle.png"),
+            _ => None,
+        }
+    }
+}
+
+/// Node showing a glyph of the [`LastDevice`], sized by whoever spawns it.
+#[derive(Component)]
+#[require(Node)]
+pub struct GlyphIcon(pub Glyph);
+
+const BADGE_COLOR: Color = Color::srgb_u8(64, 64, 64);
+
+/// Stick deflection that counts as using a gamepad.
+const STICK_THRESHOLD: f32 = 0.5;
+
+impl Plugin for GlyphPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<LastDevice>()
+            .add_systems(PreUpdate, track_last_device.after(InputSystems))
+            .add_systems(PostUpdate, update_glyph_icons.before(UiSystems::Prepare));
+    }
+}
+
+fn track_last_device(
+    mut connections: MessageReader<GamepadConnectionEvent>,
+    keyboard: Res<ButtonInput<KeyCode>>,
+    gamepads: Query<&Gamepad>,
+    mut last: ResMut<LastDevice>,
+) {
+    let connected = connections
+        .read()
+        .filter_map(|connection| match &connection.connection {
+            GamepadConnection::Connected { vendor_id, .. } => Some(*vendor_id),
+            GamepadConnection::Disconnected => None,
+        })
+        .last()
+        .map(GlyphSet::for_vendor);
+    let used = gamepads
+        .iter()
+        .find(|gamepad| {
+            gamepad.get_just_pressed().next().is_some()
+                || gamepad.left_stick().length() > STICK_THRESHOLD
+                || gamepad.right_stick().length() > STICK_THRESHOLD
+        })
+        .map(|gamepad| GlyphSet::for_vendor(gamepad.vendor_id()));
+    let typed = keyboard
+        .get_just_pressed()
+        .next()
+        .map(|_| GlyphSet::Keyboard);
+
+    if let Some(set) = typed.or(used).or(connected) {
+        last.set_if_neq(LastDevice(set));
+    }
+}
+
+fn update_glyph_icons(
+    last: Res<LastDevice>,
+    mut icons: Query<(Entity, Ref<GlyphIcon>, &mut Node)>,
+    asset_server: Res<AssetServer>,
+    mut commands: Commands,
+) {
+    for (entity, icon, mut node) in &mut icons {
+        if !last.is_changed() && !icon.is_added() {
+            continue;
+        }
+        let mut entity = commands.entity(entity);
+        entity.despawn_related::<Children>();
+        match icon.0.image(last.0) {
+            Some(path) => {
+                entity
+                    .remove::<BackgroundColor>()
+                    .insert(ImageNode::new(asset_server.load(path)));
+            }
+            None => {
+                node.justify_content = JustifyContent::Center;
+                node.align_items = AlignItems::Center;
+                node.border_radius = BorderRadius::all(px(8));
+                entity
+                    .remove::<ImageNode>()
+                    .insert(BackgroundColor(BADGE_COLOR))
+                    .with_child((
+                        Text::new(icon.0.label(last.0)),
+                        TextFont {
+                            font_size: 12.,
+                            ..default()
+                        },
+                        TextColor(Color::WHITE),
+                    ));
+            }
+        }
+    }
+}
@@ -1,57 +1,80 @@
-use avian3d::prelude::Collider;
+use avian3d::prelude::{Collider, CollisionEventsEnabled, CollisionStart, Sensor};
 use bevy::prelude::*;
 
-use crate::DespawnOnFinish;
+use crate::{
+    AppState,
+    plugins::{
+        accessibility::Swatch,
+        level::LevelGeometry,
+        net::SimulationSystems,
+        particles::{Effect, EmitEffect},
+        settings::Settings,
+        sound::{PlaySfx, Sfx},
+        stats::RunStats,
+    },
+};
 
 #[derive(Message)]
 pub struct GunShootEvent {
     pub target: Vec3,
     pub source: Vec3,
+    pub shooter: Entity,
 }
 
 #[derive(Component)]
 pub struct Bullet {
     pub target: Vec3,
+    /// Player credited with whatever this bullet hits.
+    pub owner: Entity,
 }
 
-#[derive(Resource)]
-pub struct BulletHitAudio(pub Handle<AudioSource>);
-
 pub struct GunPlugin;
 
 impl Plugin for GunPlugin {
     fn build(&self, app: &mut App) {
         app.add_message::<GunShootEvent>()
-            .add_systems(Startup, (insert_bullet_model, spawn_bullet_hit_audio))
-            .add_systems(Update, handle_bullet_move)
-            .add_systems(Update, handle_shoot);
+            .add_systems(Startup, insert_bullet_model)
+            .add_systems(
+                Update,
+                recolor_bullet_model.run_if(resource_changed::<Settings>),
+            )
+            .add_systems(Update, handle_bullet_move.in_set(SimulationSystems))
+            .add_systems(Update, handle_shoot.in_set(SimulationSystems))
+            .add_systems(Update, stop_bullets_on_level.in_set(SimulationSystems));
     }
 }
 
-fn spawn_bullet_hit_audio(asset_server: Res<AssetServer>, mut commands: Commands) {
-    commands.insert_resource(BulletHitAudio(asset_server.load("impact.ogg")));
-}
-
 const BULLET_SPEED: f32 = 8.;
 
 #[derive(Resource)]
-struct BulletModel {
+pub struct BulletModel {
     pub mesh: Mesh3d,
     pub material: MeshMaterial3d<StandardMaterial>,
 }
 
 fn insert_bullet_model(
     mut commands: Commands,
+    settings: Res<Settings>,
     mut meshes: ResMut<Assets<Mesh>>,
     mut materials: ResMut<Assets<StandardMaterial>>,
 ) {
     let model = BulletModel {
         mesh: Mesh3d(meshes.add(Cuboid::new(0.5, 0.5, 0.5))),
-        material: MeshMaterial3d(materials.add(Color::srgb_u8(255, 222, 33))),
+        material: MeshMaterial3d(materials.add(settings.palette.color(Swatch::Bullet))),
     };
     commands.insert_resource(model);
 }
 
+fn recolor_bullet_model(
+    settings: Res<Settings>,
+    model: Res<BulletModel>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    if let Some(material) = materials.get_mut(&model.material.0) {
+        material.base_color = settings.palette.color(Swatch::Bullet);
+    }
+}
+
 fn handle_bullet_move(
     mut query: Query<(Entity, &mut Transform, &Bullet)>,
     mut commands: Commands,
@@ -69,22 +92,107 @@ fn handle_bullet_move(
     }
 }
 
+/// Bullets hitting the board's components are blocked instead of flying through.
+fn stop_bullets_on_level(
+    mut collisions: MessageReader<CollisionStart>,
+    bullets: Query<&Transform, With<Bullet>>,
+    level: Query<(), With<LevelGeometry>>,
+    mut effects: MessageWriter<EmitEffect>,
+    mut commands: Commands,
+) {
+    for collision in collisions.read() {
+        let pairs = [
+            (collision.collider1, collision.body2),
+            (collision.collider2, collision.body1),
+        ];
+        for (bullet, body) in pairs {
+            if let Ok(transform) = bullets.get(bullet)
+                && body.is_some_and(|body| level.contains(body))
+            {
+                commands.entity(bullet).try_despawn();
+                effects.write(EmitEffect {
+                    effect: Effect::Spark,
+                    position: transform.translation,
+                    direction: None,
+                });
+            }
+        }
+    }
+}
+
 fn handle_shoot(
     mut commands: Commands,
     mut shoots: MessageReader<GunShootEvent>,
     bullet_model: Res<BulletModel>,
-    bullet_audio: Res<BulletHitAudio>,
+    mut sfx: MessageWriter<PlaySfx>,
+    mut stats: ResMut<RunStats>,
 ) {
     for shoot in shoots.read() {
+        stats.shots_fired += 1;
         commands.spawn((
+            DespawnOnExit(AppState::InGame),
             bullet_model.mesh.clone(),
             bullet_model.material.clone(),
             Transform::from_xyz(shoot.source.x, shoot.source.y, shoot.source.z),
             Bullet {
-                target: shoot.target.clone(),
+                target: shoot.target,
+                owner: shoot.shooter,
             },
+            // A sensor, so it neither shoves the shooter it spawns in nor teammates on its way.
+            Sensor::default(),
             Collider::cuboid(0.5, 0.5, 0.5),
+            // The board's trimesh colliders don't report collisions, the bullet has to.
+            CollisionEventsEnabled,
         ));
-        commands.spawn((AudioPlayer::new(bullet_audio.0.clone()), DespawnOnFinish));
+        sfx.write(PlaySfx(Sfx::Shot));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bullet_fired_at_a_wall_is_despawned() {
+        let mut app = App::new();
+        app.add_message::<GunShootEvent>()
+            .add_message::<CollisionStart>()
+            .add_message::<PlaySfx>()
+            .add_message::<EmitEffect>()
+            .init_resource::<RunStats>()
+            .insert_resource(BulletModel {
+                mesh: Mesh3d::default(),
+                material: MeshMaterial3d::default(),
+            })
+            .add_systems(Update, (handle_shoot, stop_bullets_on_level).chain());
+
+        let shooter = app.world_mut().spawn_empty().id();
+        let wall = app.world_mut().spawn(LevelGeometry).id();
+        let wall_mesh = app.world_mut().spawn(ChildOf(wall)).id();
+
+        app.world_mut().write_message(GunShootEvent {
+            source: Vec3::new(0., 0.5, 0.),
+            target: Vec3::new(20., 0.5, 0.),
+            shooter,
+        });
+        app.update();
+
+        let mut bullets = app
+            .world_mut()
+            .query_filtered::<Entity, (With<Bullet>, With<Sensor>, With<CollisionEventsEnabled>)>();
+        let bullet = bullets
+            .single(app.world())
+            .expect("bullets must be sensors reporting their collisions");
+
+        // What the physics step reports once the bullet reaches the board's trimesh.
+        app.world_mut().write_message(CollisionStart {
+            collider1: wall_mesh,
+            collider2: bullet,
+            body1: Some(wall),
+            body2: Some(bullet),
+        });
+        app.update();
+
+        assert!(app.world().get_entity(bullet).is_err());
     }
 }
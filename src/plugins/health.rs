@@ -1,5 +1,7 @@
 use bevy::prelude::*;
 
+use crate::plugins::net::SimulationSystems;
+
 #[derive(Component)]
 pub struct Health(pub f32);
 
@@ -7,7 +9,7 @@ pub struct HealthPlugin;
 
 impl Plugin for HealthPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(Update, kill_when_no_more_health);
+        app.add_systems(Update, kill_when_no_more_health.in_set(SimulationSystems));
     }
 }
 
@@ -0,0 +1,145 @@
+use std::{
+    cmp::Reverse,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::plugins::stats::RunStats;
+
+pub struct HighScorePlugin;
+
+/// Bumped whenever [`HighScoreFile`] changes shape; older files are set aside, not misread.
+const HIGH_SCORE_VERSION: u32 = 1;
+pub const MAX_HIGH_SCORES: usize = 10;
+pub const INITIALS_LEN: usize = 3;
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct HighScore {
+    pub initials: String,
+    pub score: u32,
+    pub waves_cleared: u32,
+    pub kills: u32,
+    pub coins_earned: u32,
+    pub time_alive: f32,
+}
+
+impl HighScore {
+    pub fn new(initials: String, stats: &RunStats) -> Self {
+        Self {
+            initials,
+            score: stats.score(),
+            waves_cleared: stats.waves_cleared,
+            kills: stats.kills,
+            coins_earned: stats.coins_earned,
+            time_alive: stats.time_alive,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct HighScoreFile {
+    version: u32,
+    entries: Vec<HighScore>,
+}
+
+/// Best runs on this machine, highest score first.
+#[derive(Resource, Default)]
+pub struct HighScores(pub Vec<HighScore>);
+
+impl HighScores {
+    pub fn qualifies(&self, score: u32) -> bool {
+        score > 0
+            && (self.0.len() < MAX_HIGH_SCORES
+                || self.0.last().is_some_and(|last| score > last.score))
+    }
+
+    /// Inserts `entry` at its rank and returns that 1-based rank, if it made the table.
+    pub fn insert(&mut self, entry: HighScore) -> Option<usize> {
+        let rank = self.0.partition_point(|other| other.score >= entry.score);
+        if rank >= MAX_HIGH_SCORES {
+            return None;
+        }
+        self.0.insert(rank, entry);
+        self.0.truncate(MAX_HIGH_SCORES);
+        Some(rank + 1)
+    }
+
+    fn path() -> Option<PathBuf> {
+        dirs::data_dir().map(|dir| dir.join("imissmyram").join("highscores.ron"))
+    }
+
+    fn load() -> Self {
+        let Some(path) = Self::path() else {
+            return Self::default();
+        };
+        let Ok(contents) = fs::read_to_string(&path) else {
+            return Self::default();
+        };
+
+        match ron::from_str::<HighScoreFile>(&contents) {
+            Ok(file) if file.version == HIGH_SCORE_VERSION => {
+                let mut scores = HighScores(file.entries);
+                scores.0.sort_by_key(|entry| Reverse(entry.score));
+                scores.0.truncate(MAX_HIGH_SCORES);
+                scores
+            }
+            Ok(file) => {
+                warn!(
+                    "High scores at {} have version {}, expected {HIGH_SCORE_VERSION}",
+                    path.display(),
+                    file.version
+                );
+                set_aside(&path);
+                Self::default()
+            }
+            Err(error) => {
+                warn!("High scores at {} are corrupted: {error}", path.display());
+                set_aside(&path);
+                Self::default()
+            }
+        }
+    }
+
+    pub fn save(&self) {
+        let Some(path) = Self::path() else {
+            warn!("No data directory, high scores will not be saved");
+            return;
+        };
+        let file = HighScoreFile {
+            version: HIGH_SCORE_VERSION,
+            entries: self.0.clone(),
+        };
+        let result = ron::ser::to_string_pretty(&file, default())
+            .map_err(|error| error.to_string())
+            .and_then(|contents| {
+                if let Some(dir) = path.parent() {
+                    fs::create_dir_all(dir).map_err(|error| error.to_string())?;
+                }
+                // Written next to the real file first, so a crash mid-write cannot truncate it.
+                let temporary = path.with_extension("ron.tmp");
+                fs::write(&temporary, contents).map_err(|error| error.to_string())?;
+                fs::rename(&temporary, &path).map_err(|error| error.to_string())
+            });
+        if let Err(error) = result {
+            warn!("Could not save high scores to {}: {error}", path.display());
+        }
+    }
+}
+
+/// Keeps an unreadable file around as `.bak` instead of overwriting it on the next save.
+fn set_aside(path: &Path) {
+    let backup = path.with_extension("ron.bak");
+    match fs::rename(path, &backup) {
+        Ok(()) => warn!("Moved it to {}, starting a new table", backup.display()),
+        Err(error) => warn!("Could not move it aside: {error}"),
+    }
+}
+
+impl Plugin for HighScorePlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(HighScores::load());
+    }
+}
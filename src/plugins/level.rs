@@ -0,0 +1,217 @@
+//! The arena, built from `Motherboard.glb`.
+//!
+//! Besides its meshes, the glTF describes the level through empties at the root of its scene:
+//! `CPU` marks what enemies walk to, `Spawner.<n>` marks where they come from and
+//! `Path.<n>.<m>` gives the waypoints of spawner `n`, visited in increasing `m` order.
+//! When the asset has none of these, the built-in layout below is used instead.
+
+use std::collections::BTreeMap;
+
+use avian3d::prelude::{ColliderConstructor, ColliderConstructorHierarchy, RigidBody};
+use bevy::{
+    asset::LoadState,
+    gltf::{Gltf, GltfNode},
+    prelude::*,
+};
+
+use crate::AppState;
+
+pub struct LevelPlugin;
+
+const LEVEL_PATH: &str = "Motherboard.glb";
+
+/// Brings the board down to the arena's size, with its surface at `y = 0`.
+const LEVEL_TRANSFORM: Transform = Transform {
+    translation: Vec3::new(0., -0.64, 0.),
+    rotation: Quat::IDENTITY,
+    scale: Vec3::splat(0.17),
+};
+
+/// Height at which actors stand above the board.
+const ACTOR_HEIGHT: f32 = 0.5;
+
+#[derive(Clone, Debug)]
+pub struct SpawnerLayout {
+    pub position: Vec3,
+    /// Waypoints toward the CPU, last one first.
+    pub path: Vec<Vec3>,
+}
+
+#[derive(Resource, Clone, Debug)]
+pub struct Level {
+    pub cpu: Vec3,
+    pub spawners: Vec<SpawnerLayout>,
+}
+
+impl Default for Level {
+    fn default() -> Self {
+        let spawner = |position: Vec3, path: [Vec3; 3]| SpawnerLayout {
+            position,
+            path: path.into(),
+        };
+        Self {
+            cpu: Vec3::new(0., ACTOR_HEIGHT, 0.),
+            spawners: vec![
+                spawner(
+                    Vec3::new(0., 0.5, -14.),
+                    [
+                        Vec3::new(-1., 0.5, -2.),
+                        Vec3::new(1.5, 0.5, -5.),
+                        Vec3::new(-2., 0.5, -9.),
+                    ],
+                ),
+                spawner(
+                    Vec3::new(0., 0.5, 14.),
+                    [
+                        Vec3::new(1., 0.5, 2.),
+                        Vec3::new(-1.5, 0.5, 5.),
+                        Vec3::new(2., 0.5, 9.),
+                    ],
+                ),
+                spawner(
+                    Vec3::new(14., 0.5, 0.),
+                    [
+                        Vec3::new(2., 0.5, 1.),
+                        Vec3::new(6., 0.5, -1.5),
+                        Vec3::new(10., 0.5, 1.),
+                    ],
+                ),
+                spawner(
+                    Vec3::new(-14., 0.5, 0.),
+                    [
+                        Vec3::new(-2., 0.5, -1.),
+                        Vec3::new(-6., 0.5, 1.5),
+                        Vec3::new(-10., 0.5, -1.),
+                    ],
+                ),
+            ],
+        }
+    }
+}
+
+impl Level {
+    /// Reads the layout from the glTF's named empties, `None` if it does not describe one.
+    fn from_gltf(gltf: &Gltf, nodes: &Assets<GltfNode>) -> Option<Self> {
+        let position = |node: &Handle<GltfNode>| {
+            let translation =
+                LEVEL_TRANSFORM.transform_point(nodes.get(node)?.transform.translation);
+            Some(translation.with_y(ACTOR_HEIGHT))
+        };
+
+        let cpu = position(gltf.named_nodes.get("CPU")?)?;
+        let mut spawners: BTreeMap<u32, SpawnerLayout> = BTreeMap::new();
+        let mut paths: BTreeMap<u32, BTreeMap<u32, Vec3>> = BTreeMap::new();
+        for (name, node) in &gltf.named_nodes {
+            let mut parts = name.split('.');
+            match (parts.next(), parts.next(), parts.next()) {
+                (Some("Spawner"), Some(index), None) => {
+                    if let (Ok(index), Some(position)) = (index.parse(), position(node)) {
+                        spawners.insert(
+                            index,
+                            SpawnerLayout {
+                                position,
+                                path: Vec::new(),
+                            },
+                        );
+                    }
+                }
+                (Some("Path"), Some(index), Some(waypoint)) => {
+                    if let (Ok(index), Ok(waypoint), Some(position)) =
+                        (index.parse(), waypoint.parse(), position(node))
+                    {
+                        paths.entry(index).or_default().insert(waypoint, position);
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        for (index, spawner) in &mut spawners {
+            if let Some(path) = paths.remove(index) {
+                spawner.path = path.into_values().rev().collect();
+            }
+        }
+        if spawners.is_empty() {
+            return None;
+        }
+        Some(Self {
+            cpu,
+            spawners: spawners.into_values().collect(),
+        })
+    }
+}
+
+#[derive(Resource)]
+struct LevelAsset(Handle<Gltf>);
+
+/// Inserted once [`Level`] holds the layout a run is played on, no run starts before.
+#[derive(Resource)]
+pub struct LevelLoaded;
+
+/// Root of the board scene, every mesh under it gets a static collider.
+#[derive(Component)]
+pub struct LevelGeometry;
+
+impl Plugin for LevelPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Level>()
+            .add_systems(Startup, load_level)
+            .add_systems(
+                Update,
+                read_level_layout.run_if(not(resource_exists::<LevelLoaded>)),
+            )
+            .add_systems(OnEnter(AppState::InGame), spawn_level);
+    }
+}
+
+fn load_level(asset_server: Res<AssetServer>, mut commands: Commands) {
+    commands.insert_resource(LevelAsset(asset_server.load(LEVEL_PATH)));
+}
+
+fn read_level_layout(
+    asset: Res<LevelAsset>,
+    asset_server: Res<AssetServer>,
+    gltfs: Res<Assets<Gltf>>,
+    nodes: Res<Assets<GltfNode>>,
+    mut level: ResMut<Level>,
+    mut commands: Commands,
+) {
+    match gltfs.get(&asset.0) {
+        Some(gltf) => match Level::from_gltf(gltf, &nodes) {
+            Some(layout) => *level = layout,
+            None => info!("{LEVEL_PATH} has no CPU and spawner empties, using the built-in layout"),
+        },
+        // Reported by `spawn_level`, which falls back to a flat floor.
+        None if asset_server.load_state(&asset.0).is_failed() => {}
+        None => return,
+    }
+    commands.insert_resource(LevelLoaded);
+}
+
+fn spawn_level(
+    asset: Res<LevelAsset>,
+    asset_server: Res<AssetServer>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut commands: Commands,
+) {
+    if let LoadState::Failed(error) = asset_server.load_state(&asset.0) {
+        warn!("Could not load {LEVEL_PATH}, falling back to a flat floor: {error}");
+        commands.spawn((
+            DespawnOnExit(AppState::InGame),
+            Mesh3d(meshes.add(Circle::new(4.0))),
+            MeshMaterial3d(materials.add(Color::WHITE)),
+            Transform::from_rotation(Quat::from_rotation_x(-std::f32::consts::FRAC_PI_2)),
+        ));
+        return;
+    }
+
+    commands.spawn((
+        DespawnOnExit(AppState::InGame),
+        LevelGeometry,
+        SceneRoot(asset_server.load(GltfAssetLabel::Scene(0).from_asset(LEVEL_PATH))),
+        LEVEL_TRANSFORM,
+        RigidBody::Static,
+        ColliderConstructorHierarchy::new(ColliderConstructor::TrimeshFromMesh),
+    ));
+}
@@ -0,0 +1,235 @@
+//! UI strings, one message file per language in `assets/locales`.
+//!
+//! A message file maps keys to either `Text("...")` or `Plural(one: "...", other: "...")`, the
+//! plural form being picked from the `count` argument by the language's own rule. `{name}` in a
+//! message is replaced by the argument called `name`.
+
+use std::{collections::HashMap, fmt::Display};
+
+use bevy::{prelude::*, ui::UiSystems};
+use serde::{Deserialize, Serialize};
+
+use crate::plugins::settings::Settings;
+
+pub struct LocalePlugin;
+
+#[derive(Serialize, Deserialize, Default, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Language {
+    #[default]
+    English,
+    French,
+}
+
+pub const LANGUAGES: &[Language] = &[Language::English, Language::French];
+
+enum PluralCategory {
+    One,
+    Other,
+}
+
+impl Language {
+    /// Name of the language in itself, so it can be found whatever the current language is.
+    pub fn native_name(self) -> &'static str {
+        match self {
+            Language::English => "English",
+            Language::French => "Français",
+        }
+    }
+
+    fn source(self) -> &'static str {
+        match self {
+            Language::English => include_str!("../../assets/locales/en.ron"),
+            Language::French => include_str!("../../assets/locales/fr.ron"),
+        }
+    }
+
+    fn plural(self, count: f64) -> PluralCategory {
+        let one = match self {
+            Language::English => count == 1.,
+            Language::French => count < 2.,
+        };
+        if one {
+            PluralCategory::One
+        } else {
+            PluralCategory::Other
+        }
+    }
+
+    fn messages(self) -> HashMap<String, Message> {
+        ron::from_str(self.source()).unwrap_or_else(|error| {
+            warn!("Messages for {self:?} are invalid: {error}");
+            HashMap::new()
+        })
+    }
+}
+
+#[derive(Deserialize, Debug)]
+enum Message {
+    Text(String),
+    Plural { one: String, other: String },
+}
+
+/// Messages of the chosen language, with English filling in for any it lacks.
+#[derive(Resource)]
+pub struct Locale {
+    language: Language,
+    messages: HashMap<String, Message>,
+    fallback: HashMap<String, Message>,
+}
+
+impl Locale {
+    pub fn new(language: Language) -> Self {
+        Self {
+            language,
+            messages: language.messages(),
+            fallback: if language == Language::English {
+                HashMap::new()
+            } else {
+                Language::English.messages()
+            },
+        }
+    }
+
+    pub fn get(&self, key: &str) -> String {
+        self.format(key, &[])
+    }
+
+    /// Message `key` with its placeholders filled from `args`, or the key itself if unknown.
+    pub fn format(&self, key: &str, args: &[(&str, &dyn Display)]) -> String {
+        let message = self.messages.get(key).or_else(|| self.fallback.get(key));
+        let template = match message {
+            None => return key.to_string(),
+            Some(Message::Text(text)) => text,
+            Some(Message::Plural { one, other }) => {
+                let count = args
+                    .iter()
+                    .find(|(name, _)| *name == "count")
+                    .and_then(|(_, value)| value.to_string().parse().ok())
+                    .unwrap_or(0.);
+                match self.language.plural(count) {
+                    PluralCategory::One => one,
+                    PluralCategory::Other => other,
+                }
+            }
+        };
+        args.iter().fold(template.clone(), |text, (name, value)| {
+            text.replace(&format!("{{{name}}}"), &value.to_string())
+        })
+    }
+}
+
+/// Text showing message `key`, written again whenever the language changes.
+#[derive(Component, Clone)]
+#[require(Text)]
+pub struct Localized {
+    key: &'static str,
+    args: Vec<(&'static str, String)>,
+}
+
+impl Localized {
+    pub fn new(key: &'static str) -> Self {
+        Self {
+            key,
+            args: Vec::new(),
+        }
+    }
+
+    pub fn with(mut self, name: &'static str, value: impl Display) -> Self {
+        self.args.push((name, value.to_string()));
+        self
+    }
+}
+
+impl Plugin for LocalePlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(Locale::new(Language::default()))
+            .add_systems(PreUpdate, apply_language)
+            .add_systems(
+                PostUpdate,
+                update_localized_texts.before(UiSystems::Prepare),
+            );
+    }
+}
+
+fn apply_language(settings: Res<Settings>, mut locale: ResMut<Locale>) {
+    if settings.is_changed() && settings.language != locale.language {
+        *locale = Locale::new(settings.language);
+    }
+}
+
+fn update_localized_texts(locale: Res<Locale>, mut texts: Query<(Ref<Localized>, &mut Text)>) {
+    for (localized, mut text) in &mut texts {
+        if !locale.is_changed() && !localized.is_changed() {
+            continue;
+        }
+        let args: Vec<(&str, &dyn Display)> = localized
+            .args
+            .iter()
+            .map(|(name, value)| (*name, value as &dyn Display))
+            .collect();
+        text.0 = locale.format(localized.key, &args);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::plugins::{
+        accessibility::PALETTES,
+        difficulty::DIFFICULTIES,
+        endless::GameMode,
+        enemy::ENEMY_KINDS,
+        powerup::POWER_UPS,
+        settings::{SETTING_FIELDS, WINDOW_SETTINGS, on_off},
+        upgrade::{UPGRADES, UpgradeEffect},
+    };
+
+    #[test]
+    fn every_locale_has_every_key() {
+        let english = Language::English.messages();
+        assert!(!english.is_empty(), "English messages failed to load");
+
+        for language in LANGUAGES {
+            let messages = language.messages();
+            let mut missing: Vec<_> = english
+                .keys()
+                .filter(|key| !messages.contains_key(*key))
+                .collect();
+            missing.sort();
+            assert!(missing.is_empty(), "{language:?} is missing {missing:?}");
+        }
+    }
+
+    #[test]
+    fn every_locale_has_the_keys_used_in_code() {
+        // Modes are cycled through from the default one.
+        let modes = std::iter::successors(Some(GameMode::default()), |mode| {
+            Some(mode.next()).filter(|next| *next != GameMode::default())
+        });
+        let keys: Vec<&str> = DIFFICULTIES
+            .iter()
+            .map(|difficulty| difficulty.key())
+            .chain(modes.map(|mode| mode.key()))
+            .chain(ENEMY_KINDS.iter().map(|kind| kind.key()))
+            .chain(POWER_UPS.iter().map(|kind| kind.key()))
+            .chain(UPGRADES.iter().map(|def| def.name_key))
+            .chain(UPGRADES.iter().filter_map(|def| match def.effect {
+                UpgradeEffect::Stat { stat, .. } => Some(stat.key()),
+                UpgradeEffect::HealCpu(_) => None,
+            }))
+            .chain(PALETTES.iter().map(|palette| palette.key()))
+            .chain(SETTING_FIELDS.iter().map(|field| field.key()))
+            .chain(WINDOW_SETTINGS.iter().map(|mode| mode.key()))
+            .chain([on_off(true), on_off(false)])
+            .collect();
+
+        for language in LANGUAGES {
+            let messages = language.messages();
+            let missing: Vec<_> = keys
+                .iter()
+                .filter(|key| !messages.contains_key(**key))
+                .collect();
+            assert!(missing.is_empty(), "{language:?} is missing {missing:?}");
+        }
+    }
+}
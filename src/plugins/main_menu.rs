@@ -0,0 +1,320 @@
+use bevy::{ecs::system::SystemParam, prelude::*};
+
+use crate::{
+    AppState,
+    plugins::{
+        difficulty::Difficulty,
+        endless::GameMode,
+        highscore::{HighScore, HighScores, MAX_HIGH_SCORES},
+        level::LevelLoaded,
+        locale::{Locale, Localized},
+        menu::{MENU_ITEM_COLOR, MenuActivated, MenuItem},
+        settings::{SETTING_FIELDS, spawn_setting_rows},
+    },
+};
+
+pub struct MainMenuPlugin;
+
+#[derive(Component)]
+enum MainMenuAction {
+    Play,
+    /// Cycles through the difficulty presets.
+    Difficulty,
+    /// Switches between standard and endless waves.
+    Mode,
+    Settings,
+    HighScores,
+    Quit,
+    Back,
+}
+
+/// Swapped between the buttons and the high-score table.
+#[derive(Component)]
+struct MainMenuContent;
+
+/// Reads "Loading" until the board's layout is known.
+#[derive(Component)]
+struct PlayLabel;
+
+#[derive(Component)]
+struct DifficultyLabel;
+
+#[derive(Component)]
+struct ModeLabel;
+
+impl Plugin for MainMenuPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnEnter(AppState::MainMenu), spawn_main_menu)
+            .add_systems(
+                Update,
+                (
+                    handle_main_menu_actions,
+                    update_play_label,
+                    update_difficulty_label,
+                    update_mode_label,
+                )
+                    .chain()
+                    .run_if(in_state(AppState::MainMenu)),
+            );
+    }
+}
+
+fn main_menu_button(order: usize, action: MainMenuAction, text: impl Bundle) -> impl Bundle {
+    (
+        MenuItem(order),
+        action,
+        Node {
+            width: px(280),
+            padding: UiRect::axes(px(16), px(12)),
+            margin: UiRect::vertical(px(6)),
+            justify_content: JustifyContent::Center,
+            ..default()
+        },
+        BackgroundColor(MENU_ITEM_COLOR),
+        children![text],
+    )
+}
+
+fn button_text(text: impl Bundle) -> impl Bundle {
+    (
+        text,
+        TextFont {
+            font_size: 22.,
+            ..default()
+        },
+    )
+}
+
+/// Play, difficulty and mode labels are written by [`update_play_label`],
+/// [`update_difficulty_label`] and [`update_mode_label`], since what they show changes.
+fn spawn_buttons(content: &mut ChildSpawnerCommands) {
+    content.spawn(main_menu_button(
+        0,
+        MainMenuAction::Play,
+        button_text((Text::default(), PlayLabel)),
+    ));
+    content.spawn(main_menu_button(
+        1,
+        MainMenuAction::Difficulty,
+        button_text((Text::default(), DifficultyLabel)),
+    ));
+    content.spawn(main_menu_button(
+        2,
+        MainMenuAction::Mode,
+        button_text((Text::default(), ModeLabel)),
+    ));
+    content.spawn(main_menu_button(
+        3,
+        MainMenuAction::Settings,
+        button_text(Localized::new("menu.settings")),
+    ));
+    content.spawn(main_menu_button(
+        4,
+        MainMenuAction::HighScores,
+        button_text(Localized::new("menu.high_scores")),
+    ));
+    content.spawn(main_menu_button(
+        5,
+        MainMenuAction::Quit,
+        button_text(Localized::new("menu.quit")),
+    ));
+}
+
+fn high_score_row(rank: usize, entry: Option<&HighScore>) -> impl Bundle {
+    let rank = format!("{rank:>2}");
+    let line = match entry {
+        Some(entry) => Localized::new("menu.high_score_row")
+            .with("rank", rank)
+            .with("initials", &entry.initials)
+            .with("score", format!("{:>7}", entry.score))
+            .with("wave", entry.waves_cleared)
+            .with("kills", entry.kills),
+        None => Localized::new("menu.high_score_empty").with("rank", rank),
+    };
+    (
+        line,
+        TextFont {
+            font_size: 18.,
+            ..default()
+        },
+        Node {
+            margin: UiRect::vertical(px(2)),
+            ..default()
+        },
+    )
+}
+
+fn spawn_high_score_table(content: &mut ChildSpawnerCommands, high_scores: &HighScores) {
+    content.spawn((
+        Localized::new("menu.high_scores"),
+        TextFont {
+            font_size: 28.,
+            ..default()
+        },
+        Node {
+            margin: UiRect::bottom(px(12)),
+            ..default()
+        },
+    ));
+    for rank in 1..=MAX_HIGH_SCORES {
+        content.spawn(high_score_row(rank, high_scores.0.get(rank - 1)));
+    }
+    content.spawn(main_menu_button(
+        0,
+        MainMenuAction::Back,
+        button_text(Localized::new("menu.back")),
+    ));
+}
+
+fn spawn_settings(content: &mut ChildSpawnerCommands) {
+    content.spawn((
+        Localized::new("menu.settings"),
+        TextFont {
+            font_size: 28.,
+            ..default()
+        },
+        Node {
+            margin: UiRect::bottom(px(12)),
+            ..default()
+        },
+    ));
+    spawn_setting_rows(content, 0);
+    content.spawn(main_menu_button(
+        SETTING_FIELDS.len(),
+        MainMenuAction::Back,
+        button_text(Localized::new("menu.back")),
+    ));
+}
+
+fn spawn_main_menu(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands
+        .spawn((
+            DespawnOnExit(AppState::MainMenu),
+            ImageNode::new(asset_server.load("Menu.png")),
+            Node {
+                position_type: PositionType::Absolute,
+                width: percent(100),
+                height: percent(100),
+                display: Display::Flex,
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                ..default()
+            },
+        ))
+        .with_children(|root| {
+            root.spawn((
+                MainMenuContent,
+                Node {
+                    display: Display::Flex,
+                    flex_direction: FlexDirection::Column,
+                    align_items: AlignItems::Center,
+                    padding: UiRect::all(px(24)),
+                    ..default()
+                },
+                BackgroundColor(Color::srgba_u8(0, 0, 0, 160)),
+            ))
+            .with_children(spawn_buttons);
+        });
+}
+
+/// Options picked in the menu before a run starts.
+#[derive(SystemParam)]
+struct RunOptions<'w> {
+    difficulty: ResMut<'w, Difficulty>,
+    mode: ResMut<'w, GameMode>,
+}
+
+/// Swaps the page shown in the menu panel.
+#[derive(SystemParam)]
+struct MenuPages<'w, 's> {
+    content: Single<'w, 's, Entity, With<MainMenuContent>>,
+    high_scores: Res<'w, HighScores>,
+    commands: Commands<'w, 's>,
+}
+
+impl MenuPages<'_, '_> {
+    fn show(&mut self, page: impl FnOnce(&mut ChildSpawnerCommands)) {
+        self.commands
+            .entity(*self.content)
+            .despawn_related::<Children>()
+            .with_children(page);
+    }
+
+    fn show_high_scores(&mut self) {
+        let high_scores = &self.high_scores;
+        self.commands
+            .entity(*self.content)
+            .despawn_related::<Children>()
+            .with_children(|content| spawn_high_score_table(content, high_scores));
+    }
+}
+
+fn handle_main_menu_actions(
+    mut activations: MessageReader<MenuActivated>,
+    actions: Query<&MainMenuAction>,
+    mut pages: MenuPages,
+    level: Option<Res<LevelLoaded>>,
+    mut options: RunOptions,
+    mut next_state: ResMut<NextState<AppState>>,
+    mut exit: MessageWriter<AppExit>,
+) {
+    for activation in activations.read() {
+        match actions.get(activation.item) {
+            Ok(MainMenuAction::Play) if level.is_some() => next_state.set(AppState::InGame),
+            // The board is still loading, as the label says.
+            Ok(MainMenuAction::Play) => {}
+            Ok(MainMenuAction::Difficulty) => {
+                *options.difficulty = options.difficulty.next();
+            }
+            Ok(MainMenuAction::Mode) => *options.mode = options.mode.next(),
+            Ok(MainMenuAction::Settings) => pages.show(spawn_settings),
+            Ok(MainMenuAction::HighScores) => pages.show_high_scores(),
+            Ok(MainMenuAction::Back) => pages.show(spawn_buttons),
+            Ok(MainMenuAction::Quit) => {
+                exit.write(AppExit::Success);
+            }
+            Err(_) => {}
+        }
+    }
+}
+
+fn update_play_label(
+    level: Option<Res<LevelLoaded>>,
+    locale: Res<Locale>,
+    mut labels: Query<(&mut Text, Ref<PlayLabel>)>,
+) {
+    let loaded = level.as_ref().is_some_and(|level| level.is_added());
+    for (mut text, label) in &mut labels {
+        if loaded || locale.is_changed() || label.is_added() {
+            text.0 = locale.get(match level {
+                Some(_) => "menu.play",
+                None => "menu.loading",
+            });
+        }
+    }
+}
+
+fn update_difficulty_label(
+    difficulty: Res<Difficulty>,
+    locale: Res<Locale>,
+    mut labels: Query<(&mut Text, Ref<DifficultyLabel>)>,
+) {
+    for (mut text, label) in &mut labels {
+        if difficulty.is_changed() || locale.is_changed() || label.is_added() {
+            let name = locale.get(difficulty.key());
+            text.0 = locale.format("menu.difficulty", &[("difficulty", &name)]);
+        }
+    }
+}
+
+fn update_mode_label(
+    mode: Res<GameMode>,
+    locale: Res<Locale>,
+    mut labels: Query<(&mut Text, Ref<ModeLabel>)>,
+) {
+    for (mut text, label) in &mut labels {
+        if mode.is_changed() || locale.is_changed() || label.is_added() {
+            text.0 = locale.format("menu.mode", &[("mode", &locale.get(mode.key()))]);
+        }
+    }
+}
@@ -0,0 +1,186 @@
+use bevy::prelude::*;
+
+use crate::plugins::accessibility::ContrastOutline;
+
+pub struct MenuPlugin;
+
+/// Focusable entry of the open menu, navigated in ascending order.
+#[derive(Component)]
+#[require(Button, ContrastOutline)]
+pub struct MenuItem(pub usize);
+
+type ItemInteractions<'w, 's> =
+    Query<'w, 's, (Entity, &'static Interaction), (Changed<Interaction>, With<MenuItem>)>;
+
+/// Keeps the D-pad from navigating or adjusting the menu while it exists, for widgets that use it
+/// themselves.
+#[derive(Component, Default)]
+pub struct CapturesDpad;
+
+#[derive(Resource, Default)]
+pub struct MenuFocus {
+    pub index: usize,
+    /// Gamepad that last navigated or confirmed, `None` when driven by the mouse.
+    pub gamepad: Option<Entity>,
+}
+
+/// Sent when a menu item is confirmed, with the gamepad that confirmed it, if any.
+#[derive(Message)]
+pub struct MenuActivated {
+    pub item: Entity,
+    pub gamepad: Option<Entity>,
+}
+
+/// Sent when left or right is pressed on the focused item, `step` is -1 or 1.
+#[derive(Message)]
+pub struct MenuAdjusted {
+    pub item: Entity,
+    pub step: i32,
+}
+
+pub const MENU_ITEM_COLOR: Color = Color::srgb_u8(50, 50, 50);
+pub const MENU_ITEM_FOCUSED_COLOR: Color = Color::srgb_u8(90, 90, 160);
+
+impl Plugin for MenuPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<MenuFocus>()
+            .add_message::<MenuActivated>()
+            .add_message::<MenuAdjusted>()
+            .add_systems(
+                Update,
+                (
+                    reset_focus_on_open,
+                    navigate_menu,
+                    activate_menu_item,
+                    adjust_menu_item,
+                    highlight_focused_item,
+                )
+                    .chain(),
+            );
+    }
+}
+
+fn sorted_items(items: &Query<(Entity, &MenuItem)>) -> Vec<Entity> {
+    let mut items: Vec<_> = items.iter().collect();
+    items.sort_by_key(|(_, item)| item.0);
+    items.into_iter().map(|(entity, _)| entity).collect()
+}
+
+fn reset_focus_on_open(added: Query<(), Added<MenuItem>>, mut focus: ResMut<MenuFocus>) {
+    if !added.is_empty() {
+        focus.index = 0;
+    }
+}
+
+fn navigate_menu(
+    gamepads: Query<(Entity, &Gamepad)>,
+    items: Query<(Entity, &MenuItem)>,
+    interactions: ItemInteractions,
+    captured: Query<(), With<CapturesDpad>>,
+    mut focus: ResMut<MenuFocus>,
+) {
+    let count = items.iter().count();
+    if count == 0 {
+        return;
+    }
+
+    for (entity, gamepad) in gamepads.iter().filter(|_| captured.is_empty()) {
+        if gamepad.just_pressed(GamepadButton::DPadDown) {
+            focus.index = (focus.index + 1) % count;
+            focus.gamepad = Some(entity);
+        }
+        if gamepad.just_pressed(GamepadButton::DPadUp) {
+            focus.index = (focus.index + count - 1) % count;
+            focus.gamepad = Some(entity);
+        }
+    }
+
+    let sorted = sorted_items(&items);
+    for (entity, interaction) in &interactions {
+        if *interaction == Interaction::Hovered
+            && let Some(index) = sorted.iter().position(|item| *item == entity)
+        {
+            focus.index = index;
+            focus.gamepad = None;
+        }
+    }
+}
+
+fn activate_menu_item(
+    gamepads: Query<(Entity, &Gamepad)>,
+    items: Query<(Entity, &MenuItem)>,
+    interactions: ItemInteractions,
+    mut focus: ResMut<MenuFocus>,
+    mut activated: MessageWriter<MenuActivated>,
+) {
+    let sorted = sorted_items(&items);
+    let Some(focused) = sorted.get(focus.index).copied() else {
+        return;
+    };
+
+    for (entity, gamepad) in &gamepads {
+        if gamepad.just_pressed(GamepadButton::South) {
+            focus.gamepad = Some(entity);
+            activated.write(MenuActivated {
+                item: focused,
+                gamepad: Some(entity),
+            });
+        }
+    }
+
+    for (entity, interaction) in &interactions {
+        if *interaction == Interaction::Pressed {
+            activated.write(MenuActivated {
+                item: entity,
+                gamepad: None,
+            });
+        }
+    }
+}
+
+fn adjust_menu_item(
+    gamepads: Query<(Entity, &Gamepad)>,
+    items: Query<(Entity, &MenuItem)>,
+    captured: Query<(), With<CapturesDpad>>,
+    mut focus: ResMut<MenuFocus>,
+    mut adjusted: MessageWriter<MenuAdjusted>,
+) {
+    let sorted = sorted_items(&items);
+    let Some(focused) = sorted.get(focus.index).copied() else {
+        return;
+    };
+    if !captured.is_empty() {
+        return;
+    }
+
+    for (entity, gamepad) in &gamepads {
+        for (button, step) in [(GamepadButton::DPadLeft, -1), (GamepadButton::DPadRight, 1)] {
+            if gamepad.just_pressed(button) {
+                focus.gamepad = Some(entity);
+                adjusted.write(MenuAdjusted {
+                    item: focused,
+                    step,
+                });
+            }
+        }
+    }
+}
+
+fn highlight_focused_item(
+    items: Query<(Entity, &MenuItem)>,
+    mut colors: Query<&mut BackgroundColor, With<MenuItem>>,
+    focus: Res<MenuFocus>,
+) {
+    for (index, entity) in sorted_items(&items).into_iter().enumerate() {
+        if let Ok(mut color) = colors.get_mut(entity) {
+            let target = if index == focus.index {
+                MENU_ITEM_FOCUSED_COLOR
+            } else {
+                MENU_ITEM_COLOR
+            };
+            if color.0 != target {
+                color.0 = target;
+            }
+        }
+    }
+}
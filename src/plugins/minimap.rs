@@ -0,0 +1,241 @@
+use std::collections::HashMap;
+
+use avian3d::prelude::ColliderAabb;
+use bevy::{ecs::system::SystemParam, prelude::*};
+
+use crate::{
+    AppState, Cpu,
+    plugins::{
+        accessibility::{ContrastOutline, Painted, Swatch},
+        camera::GameCamera,
+        enemy::{EnemyKind, EnemySpawner},
+        level::{Level, LevelGeometry},
+        player::Player,
+    },
+};
+
+pub struct MinimapPlugin;
+
+/// Corner map of the arena, its dots are absolutely positioned children.
+#[derive(Component)]
+struct Minimap;
+
+/// Outline of the board on the [`Minimap`].
+#[derive(Component)]
+struct MinimapArena;
+
+/// Dot on the [`Minimap`] standing for `0`.
+#[derive(Component)]
+struct MinimapDot(Entity);
+
+const MINIMAP_SIZE: f32 = 180.;
+/// Space kept around the arena when fitting it into the minimap.
+const MINIMAP_MARGIN: f32 = 1.;
+
+impl Plugin for MinimapPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnEnter(AppState::InGame), spawn_minimap)
+            .add_systems(
+                Update,
+                (update_minimap_arena, update_minimap).run_if(in_state(AppState::InGame)),
+            );
+    }
+}
+
+fn spawn_minimap(mut commands: Commands) {
+    commands.spawn((
+        DespawnOnExit(AppState::InGame),
+        Minimap,
+        Node {
+            position_type: PositionType::Absolute,
+            top: px(120),
+            right: px(10),
+            width: px(MINIMAP_SIZE),
+            height: px(MINIMAP_SIZE),
+            border: UiRect::all(px(2)),
+            ..default()
+        },
+        BackgroundColor(Color::srgba_u8(0, 0, 0, 140)),
+        BorderColor::all(Color::srgb_u8(240, 240, 240)),
+        ContrastOutline,
+        children![(
+            MinimapArena,
+            Node {
+                position_type: PositionType::Absolute,
+                border: UiRect::all(px(1)),
+                ..default()
+            },
+            BorderColor::all(Color::srgba_u8(240, 240, 240, 160)),
+        )],
+    ));
+}
+
+/// Maps arena positions to fractions of the minimap, laid out like the screen.
+struct MinimapProjection {
+    right: Vec3,
+    up: Vec3,
+    extent: f32,
+}
+
+impl MinimapProjection {
+    fn project(&self, position: Vec3) -> Vec2 {
+        Vec2::new(
+            0.5 + position.dot(self.right) / self.extent / 2.,
+            0.5 - position.dot(self.up) / self.extent / 2.,
+        )
+        .clamp(Vec2::ZERO, Vec2::ONE)
+    }
+}
+
+/// What the minimap is fitted to: the camera's heading and the arena's bounds.
+#[derive(SystemParam)]
+struct MinimapView<'w, 's> {
+    camera: Single<'w, 's, &'static GlobalTransform, With<GameCamera>>,
+    level: Res<'w, Level>,
+    board: Query<'w, 's, Entity, With<LevelGeometry>>,
+    children: Query<'w, 's, &'static Children>,
+    aabbs: Query<'w, 's, &'static ColliderAabb>,
+}
+
+impl MinimapView<'_, '_> {
+    /// Bounds of the board on the ground plane, `x` and `z` world coordinates.
+    ///
+    /// Taken from the board's colliders, or from the level layout while they are not built yet
+    /// or when the board failed to load.
+    fn arena(&self) -> Rect {
+        let colliders = self
+            .board
+            .iter()
+            .flat_map(|board| self.children.iter_descendants(board))
+            .filter_map(|entity| self.aabbs.get(entity).ok())
+            .map(|aabb| Rect::new(aabb.min.x, aabb.min.z, aabb.max.x, aabb.max.z))
+            .reduce(|bounds, aabb| bounds.union(aabb));
+        colliders.unwrap_or_else(|| {
+            let level = &self.level;
+            level
+                .spawners
+                .iter()
+                .flat_map(|spawner| spawner.path.iter().chain([&spawner.position]))
+                .chain([&level.cpu])
+                .fold(
+                    Rect::from_center_size(level.cpu.xz(), Vec2::ZERO),
+                    |bounds, point| bounds.union_point(point.xz()),
+                )
+        })
+    }
+
+    fn projection(&self, arena: Rect) -> MinimapProjection {
+        MinimapProjection {
+            right: self.camera.right().with_y(0.).normalize_or_zero(),
+            up: self.camera.up().with_y(0.).normalize_or_zero(),
+            extent: arena.min.abs().max(arena.max.abs()).max_element() + MINIMAP_MARGIN,
+        }
+    }
+}
+
+fn update_minimap_arena(view: MinimapView, mut outline: Single<&mut Node, With<MinimapArena>>) {
+    let arena = view.arena();
+    let projection = view.projection(arena);
+    let corners = [
+        arena.min,
+        Vec2::new(arena.min.x, arena.max.y),
+        Vec2::new(arena.max.x, arena.min.y),
+        arena.max,
+    ]
+    .map(|corner| projection.project(Vec3::new(corner.x, 0., corner.y)));
+    let min = corners.into_iter().reduce(Vec2::min).unwrap_or_default();
+    let max = corners.into_iter().reduce(Vec2::max).unwrap_or_default();
+
+    outline.left = percent(min.x * 100.);
+    outline.top = percent(min.y * 100.);
+    outline.width = percent((max.x - min.x) * 100.);
+    outline.height = percent((max.y - min.y) * 100.);
+}
+
+fn minimap_dot(target: Entity, size: f32, swatch: Swatch, point: Vec2) -> impl Bundle {
+    (
+        MinimapDot(target),
+        Node {
+            position_type: PositionType::Absolute,
+            left: percent(point.x * 100.),
+            top: percent(point.y * 100.),
+            width: px(size),
+            height: px(size),
+            margin: UiRect::all(px(-size / 2.)),
+            border_radius: BorderRadius::all(percent(50)),
+            ..default()
+        },
+        Painted::Background(swatch),
+    )
+}
+
+/// Entities shown on the minimap.
+#[derive(SystemParam)]
+struct MinimapTargets<'w, 's> {
+    enemies: Query<'w, 's, (Entity, &'static EnemyKind)>,
+    players: Query<'w, 's, (Entity, &'static Player)>,
+    cpu: Query<'w, 's, Entity, With<Cpu>>,
+    spawners: Query<'w, 's, (Entity, &'static EnemySpawner)>,
+    transforms: Query<'w, 's, &'static GlobalTransform>,
+}
+
+/// Adds a dot for everything worth tracking, removes dots whose entity is gone and moves the rest.
+fn update_minimap(
+    minimap: Single<Entity, With<Minimap>>,
+    view: MinimapView,
+    targets: MinimapTargets,
+    mut dots: Query<(Entity, &MinimapDot, &mut Node)>,
+    mut commands: Commands,
+) {
+    let projection = view.projection(view.arena());
+
+    let MinimapTargets {
+        enemies,
+        players,
+        cpu,
+        spawners,
+        transforms,
+    } = targets;
+    let position = |target: Entity, fixed: Option<Vec3>| {
+        fixed.or_else(|| {
+            transforms
+                .get(target)
+                .ok()
+                .map(GlobalTransform::translation)
+        })
+    };
+    let mut tracked: HashMap<Entity, (Option<Vec3>, f32, Swatch)> = HashMap::new();
+    for (entity, spawner) in &spawners {
+        tracked.insert(entity, (Some(spawner.position), 10., Swatch::Spawner));
+    }
+    for entity in &cpu {
+        tracked.insert(entity, (None, 12., Swatch::Cpu));
+    }
+    for (entity, kind) in &enemies {
+        tracked.insert(entity, (None, 6., Swatch::Enemy(*kind)));
+    }
+    for (entity, player) in &players {
+        tracked.insert(entity, (None, 9., Swatch::Player(player.number)));
+    }
+
+    for (dot, MinimapDot(target), mut node) in &mut dots {
+        let Some((fixed, ..)) = tracked.remove(target) else {
+            commands.entity(dot).despawn();
+            continue;
+        };
+        let Some(position) = position(*target, fixed) else {
+            continue;
+        };
+        let point = projection.project(position);
+        node.left = percent(point.x * 100.);
+        node.top = percent(point.y * 100.);
+    }
+
+    // Placed right away, so a new dot never shows up in the corner for a frame.
+    for (target, (fixed, size, swatch)) in tracked {
+        if let Some(position) = position(target, fixed) {
+            let point = projection.project(position);
+            commands.spawn((ChildOf(*minimap), minimap_dot(target, size, swatch, point)));
+        }
+    }
+}
@@ -1,5 +1,32 @@
+pub mod accessibility;
+pub mod binding;
+pub mod camera;
+pub mod difficulty;
+pub mod endless;
 pub mod enemy;
+pub mod game_over;
+pub mod glyphs;
 pub mod gun;
 pub mod health;
+pub mod highscore;
+pub mod level;
+pub mod locale;
+pub mod main_menu;
+pub mod menu;
+pub mod minimap;
+pub mod model;
+pub mod music;
+pub mod net;
+pub mod particles;
+pub mod pause;
+pub mod pickup;
 pub mod player;
+pub mod powerup;
+pub mod settings;
+pub mod shop;
+pub mod sound;
+pub mod stats;
+pub mod tutorial;
 pub mod ui;
+pub mod upgrade;
+pub mod wave;
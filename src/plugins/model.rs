@@ -0,0 +1,558 @@
+use std::{collections::HashMap, time::Duration};
+
+use bevy::{gltf::Gltf, prelude::*, scene::SceneInstanceReady};
+
+use crate::{
+    AppState,
+    plugins::{health::Health, player::PlayerInput},
+};
+
+pub struct ModelPlugin;
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum ModelKind {
+    Chara,
+    Cockroach,
+}
+
+const MODEL_KINDS: &[ModelKind] = &[ModelKind::Chara, ModelKind::Cockroach];
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum AnimState {
+    Idle,
+    Run,
+    Shoot,
+    Hit,
+    Die,
+}
+
+const ANIM_STATES: &[AnimState] = &[
+    AnimState::Idle,
+    AnimState::Run,
+    AnimState::Shoot,
+    AnimState::Hit,
+    AnimState::Die,
+];
+
+impl AnimState {
+    /// State played instead when a model has no clip for this one.
+    fn fallback(self) -> Option<AnimState> {
+        match self {
+            AnimState::Idle | AnimState::Die => None,
+            AnimState::Run | AnimState::Shoot | AnimState::Hit => Some(AnimState::Idle),
+        }
+    }
+
+    /// First of this state and its fallbacks `available` accepts.
+    fn resolve(self, available: impl Fn(AnimState) -> bool) -> Option<AnimState> {
+        let mut state = self;
+        loop {
+            if available(state) {
+                return Some(state);
+            }
+            state = state.fallback()?;
+        }
+    }
+
+    /// Looping states play until replaced, the others play once and hold until they end.
+    fn looping(self) -> bool {
+        matches!(self, AnimState::Idle | AnimState::Run)
+    }
+}
+
+impl ModelKind {
+    fn path(self) -> &'static str {
+        match self {
+            ModelKind::Chara => "Chara.glb",
+            ModelKind::Cockroach => "Cockroach.glb",
+        }
+    }
+
+    /// Places the scene inside the unit cuboid hitbox of its entity.
+    fn transform(self) -> Transform {
+        match self {
+            ModelKind::Chara => Transform::from_xyz(0., -0.4, 0.).with_scale(Vec3::splat(5.)),
+            ModelKind::Cockroach => Transform::from_xyz(0., -0.5, 0.)
+                .with_rotation(Quat::from_rotation_y(std::f32::consts::FRAC_PI_2))
+                .with_scale(Vec3::splat(4.)),
+        }
+    }
+
+    /// glTF animation names for `state`, the first one found is used.
+    fn clip_names(self, state: AnimState) -> &'static [&'static str] {
+        match (self, state) {
+            (ModelKind::Cockroach, AnimState::Run) => &["2.Walk.Fix", "Run", "Walk"],
+            (_, AnimState::Idle) => &["Idle"],
+            (_, AnimState::Run) => &["Run"],
+            (_, AnimState::Shoot) => &["Shoot"],
+            (_, AnimState::Hit) => &["Hit"],
+            (_, AnimState::Die) => &["Die"],
+        }
+    }
+
+    /// Name of the clip played for `state` among the `animations` the glTF has.
+    fn clip_name(
+        self,
+        state: AnimState,
+        animations: impl Fn(&str) -> bool,
+    ) -> Option<&'static str> {
+        self.clip_names(state)
+            .iter()
+            .copied()
+            .find(|name| animations(name))
+    }
+}
+
+/// Renders its entity with a glTF scene; the entity's own mesh is hidden once the scene is ready.
+///
+/// Gameplay only ever looks at the entity's collider, the scene is purely visual.
+#[derive(Component, Clone, Copy)]
+#[require(Tint)]
+pub struct Model {
+    pub kind: ModelKind,
+    pub scale: f32,
+}
+
+impl Model {
+    pub fn new(kind: ModelKind) -> Self {
+        Self { kind, scale: 1. }
+    }
+
+    pub fn scaled(self, scale: f32) -> Self {
+        Self { scale, ..self }
+    }
+}
+
+/// Color multiplied into every material of a [`Model`]'s scene, so that players and enemy kinds
+/// still look apart once their placeholder is gone.
+#[derive(Component, Clone, Copy, PartialEq, Debug)]
+pub struct Tint(pub Color);
+
+impl Default for Tint {
+    fn default() -> Self {
+        Self(Color::WHITE)
+    }
+}
+
+/// Untinted material of a mesh inside a model scene.
+#[derive(Component)]
+struct SceneMaterial(Handle<StandardMaterial>);
+
+/// Tinted copies of scene materials, shared by every instance with the same tint.
+#[derive(Resource, Default)]
+struct TintedMaterials(HashMap<(AssetId<StandardMaterial>, [u32; 4]), Handle<StandardMaterial>>);
+
+impl TintedMaterials {
+    fn get(
+        &mut self,
+        original: &Handle<StandardMaterial>,
+        tint: Color,
+        materials: &mut Assets<StandardMaterial>,
+    ) -> Option<Handle<StandardMaterial>> {
+        let tint = tint.to_linear();
+        if tint == LinearRgba::WHITE {
+            return Some(original.clone());
+        }
+        let key = (original.id(), tint.to_f32_array().map(f32::to_bits));
+        if let Some(handle) = self.0.get(&key) {
+            return Some(handle.clone());
+        }
+        let mut material = materials.get(original)?.clone();
+        material.base_color =
+            LinearRgba::from_vec4(material.base_color.to_linear().to_vec4() * tint.to_vec4())
+                .into();
+        let handle = materials.add(material);
+        self.0.insert(key, handle.clone());
+        Some(handle)
+    }
+}
+
+/// Scene spawned for a [`Model`], or for a corpse playing its death.
+#[derive(Component)]
+struct ModelScene(ModelKind);
+
+#[derive(Component)]
+struct Corpse(Timer);
+
+struct ModelGraph {
+    graph: Handle<AnimationGraph>,
+    nodes: HashMap<AnimState, (AnimationNodeIndex, f32)>,
+}
+
+#[derive(Resource)]
+struct ModelAssets {
+    gltfs: HashMap<ModelKind, Handle<Gltf>>,
+    graphs: HashMap<ModelKind, ModelGraph>,
+}
+
+impl ModelAssets {
+    /// Clip node for `state`, walking down the fallbacks when the model lacks it.
+    fn node(
+        &self,
+        kind: ModelKind,
+        state: AnimState,
+    ) -> Option<(AnimState, AnimationNodeIndex, f32)> {
+        let graph = self.graphs.get(&kind)?;
+        let state = state.resolve(|state| graph.nodes.contains_key(&state))?;
+        let (node, duration) = graph.nodes[&state];
+        Some((state, node, duration))
+    }
+}
+
+/// Drives the animation player found in the entity's scene.
+#[derive(Component)]
+struct Animator {
+    player: Entity,
+    kind: ModelKind,
+    current: Option<AnimState>,
+    /// Time left before a one-shot state may be replaced.
+    hold: Timer,
+}
+
+const ANIMATION_BLEND: Duration = Duration::from_millis(150);
+/// Movement stick deflection above which a player counts as running.
+const RUN_THRESHOLD: f32 = 0.2;
+
+impl Plugin for ModelPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<TintedMaterials>()
+            .add_systems(Startup, load_models)
+            .add_systems(
+                Update,
+                (
+                    build_animation_graphs,
+                    attach_models,
+                    drive_animations,
+                    despawn_corpses,
+                ),
+            )
+            .add_systems(PostUpdate, tint_scenes)
+            .add_observer(spawn_corpse);
+    }
+}
+
+fn load_models(asset_server: Res<AssetServer>, mut commands: Commands) {
+    commands.insert_resource(ModelAssets {
+        gltfs: MODEL_KINDS
+            .iter()
+            .map(|kind| (*kind, asset_server.load(kind.path())))
+            .collect(),
+        graphs: HashMap::new(),
+    });
+}
+
+fn build_animation_graphs(
+    mut models: ResMut<ModelAssets>,
+    gltfs: Res<Assets<Gltf>>,
+    clips: Res<Assets<AnimationClip>>,
+    mut graphs: ResMut<Assets<AnimationGraph>>,
+) {
+    if models.graphs.len() == MODEL_KINDS.len() {
+        return;
+    }
+
+    for kind in MODEL_KINDS {
+        if models.graphs.contains_key(kind) {
+            continue;
+        }
+        let Some(gltf) = gltfs.get(&models.gltfs[kind]) else {
+            continue;
+        };
+
+        let mut graph = AnimationGraph::new();
+        let mut nodes = HashMap::new();
+        for state in ANIM_STATES {
+            let clip = kind
+                .clip_name(*state, |name| gltf.named_animations.contains_key(name))
+                .and_then(|name| gltf.named_animations.get(name));
+            if let Some(clip) = clip {
+                let duration = clips.get(clip).map_or(0., AnimationClip::duration);
+                let node = graph.add_clip(clip.clone(), 1., graph.root);
+                nodes.insert(*state, (node, duration));
+            }
+        }
+        if nodes.is_empty() {
+            info!(
+                "{} has no usable animations, it will stay static",
+                kind.path()
+            );
+        }
+        models.graphs.insert(
+            *kind,
+            ModelGraph {
+                graph: graphs.add(graph),
+                nodes,
+            },
+        );
+    }
+}
+
+fn attach_models(
+    models: Query<(Entity, &Model), Added<Model>>,
+    asset_server: Res<AssetServer>,
+    mut commands: Commands,
+) {
+    for (entity, model) in &models {
+        let mut transform = model.kind.transform();
+        transform.scale *= model.scale;
+        transform.translation *= model.scale;
+        commands
+            .spawn((
+                ChildOf(entity),
+                ModelScene(model.kind),
+                SceneRoot(
+                    asset_server.load(GltfAssetLabel::Scene(0).from_asset(model.kind.path())),
+                ),
+                transform,
+            ))
+            .observe(on_model_ready);
+    }
+}
+
+/// Swaps the placeholder cuboid for the scene and hooks up its animation player, if any.
+fn on_model_ready(
+    ready: On<SceneInstanceReady>,
+    scenes: Query<(&ModelScene, Option<&ChildOf>, Has<Corpse>)>,
+    children: Query<&Children>,
+    scene_meshes: Query<&MeshMaterial3d<StandardMaterial>>,
+    players: Query<(), With<AnimationPlayer>>,
+    models: Res<ModelAssets>,
+    mut commands: Commands,
+) {
+    let Ok((ModelScene(kind), parent, corpse)) = scenes.get(ready.entity) else {
+        return;
+    };
+    if let Some(ChildOf(parent)) = parent {
+        commands
+            .entity(*parent)
+            .remove::<(Mesh3d, MeshMaterial3d<StandardMaterial>)>();
+    }
+    // Tinted by `tint_scenes`, the originals are shared with every other instance.
+    for entity in children.iter_descendants(ready.entity) {
+        if let Ok(material) = scene_meshes.get(entity) {
+            commands
+                .entity(entity)
+                .insert(SceneMaterial(material.0.clone()));
+        }
+    }
+
+    let player = children
+        .iter_descendants(ready.entity)
+        .find(|entity| players.contains(*entity));
+    let (Some(player), Some(graph)) = (player, models.graphs.get(kind)) else {
+        if corpse {
+            commands.entity(ready.entity).despawn();
+        }
+        return;
+    };
+    commands.entity(player).insert((
+        AnimationGraphHandle(graph.graph.clone()),
+        AnimationTransitions::new(),
+    ));
+
+    let animator = Animator {
+        player,
+        kind: *kind,
+        current: None,
+        hold: Timer::default(),
+    };
+    match (corpse, parent) {
+        (true, _) => {
+            commands.entity(ready.entity).insert(animator);
+        }
+        (false, Some(ChildOf(parent))) => {
+            commands.entity(*parent).insert(animator);
+        }
+        (false, None) => {}
+    }
+}
+
+/// Gives scene meshes the material tinted like their model, when a scene is ready or a tint changes.
+fn tint_scenes(
+    tints: Query<(Entity, Ref<Tint>)>,
+    ready: Query<Entity, Added<SceneMaterial>>,
+    ancestors: Query<&ChildOf>,
+    children: Query<&Children>,
+    mut meshes: Query<(&SceneMaterial, &mut MeshMaterial3d<StandardMaterial>)>,
+    mut tinted: ResMut<TintedMaterials>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    let mut apply = |mesh: Entity, tint: Color| {
+        let Ok((original, mut material)) = meshes.get_mut(mesh) else {
+            return;
+        };
+        if let Some(handle) = tinted.get(&original.0, tint, &mut materials)
+            && material.0 != handle
+        {
+            material.0 = handle;
+        }
+    };
+
+    for (model, tint) in &tints {
+        if tint.is_changed() {
+            for mesh in children.iter_descendants(model) {
+                apply(mesh, tint.0);
+            }
+        }
+    }
+    for mesh in &ready {
+        // Corpses carry their tint on the scene root itself.
+        let tint = ancestors
+            .iter_ancestors(mesh)
+            .find_map(|entity| tints.get(entity).ok());
+        if let Some((_, tint)) = tint {
+            apply(mesh, tint.0);
+        }
+    }
+}
+
+type Animators<'w, 's> = Query<
+    'w,
+    's,
+    (
+        &'static mut Animator,
+        Option<&'static PlayerInput>,
+        Option<Ref<'static, Health>>,
+        Has<Corpse>,
+    ),
+>;
+
+/// Picks the state every animated entity should be in from what it is doing.
+fn drive_animations(
+    time: Res<Time>,
+    models: Res<ModelAssets>,
+    mut animators: Animators,
+    mut players: Query<(&mut AnimationPlayer, &mut AnimationTransitions)>,
+) {
+    for (mut animator, input, health, corpse) in &mut animators {
+        animator.hold.tick(time.delta());
+
+        let wanted = if corpse {
+            AnimState::Die
+        } else if health
+            .as_ref()
+            .is_some_and(|health| health.is_changed() && !health.is_added())
+        {
+            AnimState::Hit
+        } else if let Some(input) = input {
+            if input.shoot {
+                AnimState::Shoot
+            } else if input.movement.length() > RUN_THRESHOLD {
+                AnimState::Run
+            } else {
+                AnimState::Idle
+            }
+        } else {
+            // Enemies, replicated ones included, are always on the move.
+            AnimState::Run
+        };
+
+        let Some((state, node, duration)) = models.node(animator.kind, wanted) else {
+            continue;
+        };
+        // A one-shot plays to its end unless another one-shot replaces it.
+        let busy = !animator.hold.is_finished();
+        if (animator.current == Some(state) || busy) && (state.looping() || busy) {
+            continue;
+        }
+
+        let Ok((mut player, mut transitions)) = players.get_mut(animator.player) else {
+            continue;
+        };
+        let animation = transitions.play(&mut player, node, ANIMATION_BLEND);
+        if state.looping() {
+            animation.repeat();
+            animator.hold = Timer::default();
+        } else {
+            animator.hold = Timer::from_seconds(duration, TimerMode::Once);
+        }
+        animator.current = Some(state);
+    }
+}
+
+/// Leaves the death animation of a killed entity playing after the entity itself is gone.
+fn spawn_corpse(
+    despawned: On<Despawn, Model>,
+    models: Query<(&Model, &Tint, &Health, &GlobalTransform)>,
+    state: Res<State<AppState>>,
+    asset_server: Res<AssetServer>,
+    assets: Res<ModelAssets>,
+    mut commands: Commands,
+) {
+    let Ok((model, tint, health, transform)) = models.get(despawned.entity) else {
+        return;
+    };
+    if health.0 > 0. || *state.get() != AppState::InGame {
+        return;
+    }
+    let Some((AnimState::Die, _, duration)) = assets.node(model.kind, AnimState::Die) else {
+        return;
+    };
+
+    let scene = model.kind.transform();
+    let transform = transform.compute_transform()
+        * Transform::from_translation(scene.translation * model.scale)
+            .with_rotation(scene.rotation)
+            .with_scale(scene.scale * model.scale);
+    commands
+        .spawn((
+            DespawnOnExit(AppState::InGame),
+            ModelScene(model.kind),
+            Corpse(Timer::from_seconds(duration, TimerMode::Once)),
+            *tint,
+            SceneRoot(asset_server.load(GltfAssetLabel::Scene(0).from_asset(model.kind.path()))),
+            transform,
+        ))
+        .observe(on_model_ready);
+}
+
+fn despawn_corpses(
+    time: Res<Time>,
+    mut corpses: Query<(Entity, &mut Corpse)>,
+    mut commands: Commands,
+) {
+    for (entity, mut corpse) in &mut corpses {
+        if corpse.0.tick(time.delta()).just_finished() {
+            commands.entity(entity).despawn();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// State actually played for `state` by a `kind` model whose glTF has `animations`.
+    fn played(kind: ModelKind, animations: &[&str], state: AnimState) -> Option<&'static str> {
+        let clip = |state| kind.clip_name(state, |name| animations.contains(&name));
+        state.resolve(|state| clip(state).is_some()).and_then(clip)
+    }
+
+    #[test]
+    fn missing_clips_fall_back_or_stay_static() {
+        // What the shipped cockroach has: a walk cycle and nothing else.
+        let cockroach = ["2.Walk.Fix", "2.Walk.Fix.001"];
+        assert_eq!(
+            played(ModelKind::Cockroach, &cockroach, AnimState::Run),
+            Some("2.Walk.Fix")
+        );
+        for state in [AnimState::Idle, AnimState::Hit, AnimState::Die] {
+            assert_eq!(played(ModelKind::Cockroach, &cockroach, state), None);
+        }
+
+        // The shipped character has no clips at all.
+        for state in ANIM_STATES {
+            assert_eq!(played(ModelKind::Chara, &[], *state), None);
+        }
+
+        let idle_only = ["Idle"];
+        for state in [AnimState::Run, AnimState::Shoot, AnimState::Hit] {
+            assert_eq!(played(ModelKind::Chara, &idle_only, state), Some("Idle"));
+        }
+        assert_eq!(played(ModelKind::Chara, &idle_only, AnimState::Die), None);
+        assert_eq!(
+            played(ModelKind::Cockroach, &["Walk", "Run"], AnimState::Run),
+            Some("Run")
+        );
+    }
+}
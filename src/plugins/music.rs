@@ -0,0 +1,124 @@
+use bevy::{audio::Volume, prelude::*};
+
+use crate::{
+    AppState, Cpu,
+    plugins::{enemy::Enemy, health::Health, settings::Settings, sound::AudioBus, wave::GamePhase},
+};
+
+pub struct MusicPlugin;
+
+#[derive(Component, Clone, Copy, PartialEq, Eq, Debug)]
+enum MusicTrack {
+    Menu,
+    Intermission,
+    /// Base combat layer, always on during a wave.
+    Combat,
+    /// Layered on top of [`MusicTrack::Combat`] while the fight goes badly.
+    CombatIntense,
+}
+
+const MUSIC_TRACKS: &[MusicTrack] = &[
+    MusicTrack::Menu,
+    MusicTrack::Intermission,
+    MusicTrack::Combat,
+    MusicTrack::CombatIntense,
+];
+
+impl MusicTrack {
+    /// The shipped tracks are placeholder loops sequenced from the sound effects.
+    fn path(self) -> &'static str {
+        match self {
+            MusicTrack::Menu => "music/menu.ogg",
+            MusicTrack::Intermission => "music/intermission.ogg",
+            MusicTrack::Combat => "music/combat.ogg",
+            MusicTrack::CombatIntense => "music/combat_intense.ogg",
+        }
+    }
+}
+
+/// Current loudness of a track, from 0 to 1, eased towards `target`.
+#[derive(Component, Default)]
+struct MusicFade {
+    level: f32,
+    target: f32,
+}
+
+const CROSSFADE_SECS: f32 = 1.5;
+/// Alive enemies at which combat is considered as intense as it gets.
+const INTENSE_ENEMY_COUNT: f32 = 20.;
+/// The intense layer fades in above this intensity and out below the lower one.
+const INTENSE_ON: f32 = 0.6;
+const INTENSE_OFF: f32 = 0.4;
+
+impl Plugin for MusicPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Startup, spawn_music)
+            .add_systems(Update, (pick_music, fade_music).chain());
+    }
+}
+
+/// Every track loops from startup, muted, so layers stay in sync and fades never restart them.
+fn spawn_music(asset_server: Res<AssetServer>, mut commands: Commands) {
+    for track in MUSIC_TRACKS {
+        commands.spawn((
+            *track,
+            MusicFade::default(),
+            AudioPlayer::new(asset_server.load(track.path())),
+            PlaybackSettings::LOOP.with_volume(Volume::SILENT),
+        ));
+    }
+}
+
+/// How badly the current wave is going, from 0 to 1.
+fn combat_intensity(enemies: usize, cpu_health: Option<f32>) -> f32 {
+    let crowd = enemies as f32 / INTENSE_ENEMY_COUNT;
+    let danger = cpu_health.map_or(0., |health| 1. - health / 100.);
+    crowd.max(danger).clamp(0., 1.)
+}
+
+fn pick_music(
+    app_state: Res<State<AppState>>,
+    phase: Option<Res<State<GamePhase>>>,
+    enemies: Query<(), With<Enemy>>,
+    cpu: Query<&Health, With<Cpu>>,
+    mut tracks: Query<(&MusicTrack, &mut MusicFade)>,
+) {
+    let intensity = combat_intensity(
+        enemies.iter().count(),
+        cpu.single().ok().map(|health| health.0),
+    );
+    let intense = tracks
+        .iter()
+        .any(|(track, fade)| *track == MusicTrack::CombatIntense && fade.target > 0.);
+    let intense = if intense {
+        intensity >= INTENSE_OFF
+    } else {
+        intensity >= INTENSE_ON
+    };
+
+    let playing: &[MusicTrack] = match (app_state.get(), phase.as_deref().map(State::get)) {
+        (AppState::InGame, Some(GamePhase::Shop)) => &[MusicTrack::Intermission],
+        (AppState::InGame, _) if intense => &[MusicTrack::Combat, MusicTrack::CombatIntense],
+        (AppState::InGame, _) => &[MusicTrack::Combat],
+        (AppState::MainMenu | AppState::GameOver, _) => &[MusicTrack::Menu],
+    };
+    for (track, mut fade) in &mut tracks {
+        let target = if playing.contains(track) { 1. } else { 0. };
+        if fade.target != target {
+            fade.target = target;
+        }
+    }
+}
+
+fn fade_music(
+    time: Res<Time<Real>>,
+    settings: Res<Settings>,
+    mut tracks: Query<(&mut MusicFade, &mut AudioSink)>,
+) {
+    let step = time.delta_secs() / CROSSFADE_SECS;
+    let volume = Volume::Linear(settings.master_volume) * settings.bus_volume(AudioBus::Music);
+    for (mut fade, mut sink) in &mut tracks {
+        fade.level += (fade.target - fade.level).clamp(-step, step);
+        sink.set_volume(volume * Volume::Linear(fade.level));
+    }
+}
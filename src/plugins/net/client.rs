@@ -0,0 +1,537 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    io::ErrorKind,
+    net::{SocketAddr, UdpSocket},
+};
+
+use bevy::{ecs::system::SystemParam, prelude::*};
+
+use crate::{
+    AppState, Cpu,
+    plugins::{
+        enemy::EnemyModel,
+        glyphs::{GlyphSet, LastDevice},
+        gun::BulletModel,
+        health::Health,
+        model::{Model, ModelKind},
+        net::{
+            NetRequest, NetRole,
+            protocol::{ClientPacket, NetEntity, NetInput, NetKind, ServerPacket, Snapshot},
+        },
+        particles::EmitEffect,
+        pickup::PickupModels,
+        player::{Player, PlayerGamepad, PlayerInput, spawn_player},
+        powerup::PowerUpModels,
+        stats::RunStats,
+        upgrade::{UPGRADES, UpgradeDef},
+        wave::{GamePhase, Wave},
+    },
+};
+
+/// How far behind the host clock replicas are drawn, so there is a later snapshot to blend toward.
+const INTERPOLATION_DELAY: f64 = 0.1;
+/// Past this gap between the local estimate and the host clock, the estimate is reset.
+const MAX_CLOCK_DRIFT: f64 = 0.25;
+const HELLO_INTERVAL: f32 = 1.;
+const HOST_TIMEOUT: f64 = 5.;
+const MAX_BUFFERED_SNAPSHOTS: usize = 32;
+
+#[derive(Resource)]
+struct ClientSocket(UdpSocket);
+
+#[derive(Resource)]
+struct Connection {
+    /// Seat given by the host, `None` until it answered.
+    player_number: Option<u8>,
+    hello: Timer,
+    last_received: f64,
+}
+
+/// Parts received so far of a snapshot split across several datagrams.
+struct PartialSnapshot {
+    snapshot: Snapshot,
+    parts: u8,
+    received: Vec<u8>,
+}
+
+#[derive(Resource, Default)]
+struct SnapshotBuffer {
+    snapshots: VecDeque<Snapshot>,
+    /// Newest snapshot still missing parts, dropped if a newer one starts arriving.
+    partial: Option<PartialSnapshot>,
+    /// Estimate of the current host time.
+    clock: Option<f64>,
+    /// Host time of the last snapshot whose effects were played.
+    played: f64,
+}
+
+impl SnapshotBuffer {
+    /// Adds a part of a snapshot, buffering the snapshot once every part has arrived.
+    fn receive(&mut self, part: u8, parts: u8, snapshot: Snapshot) {
+        // Datagrams can arrive out of order, older snapshots are useless.
+        if self
+            .snapshots
+            .back()
+            .is_some_and(|latest| latest.time >= snapshot.time)
+        {
+            return;
+        }
+        match &mut self.partial {
+            Some(partial) if partial.snapshot.time == snapshot.time => {
+                if partial.received.contains(&part) {
+                    return;
+                }
+                partial.received.push(part);
+                partial.snapshot.merge(snapshot);
+            }
+            Some(partial) if partial.snapshot.time > snapshot.time => return,
+            _ => {
+                self.partial = Some(PartialSnapshot {
+                    snapshot,
+                    parts,
+                    received: vec![part],
+                })
+            }
+        }
+
+        if let Some(partial) = self
+            .partial
+            .take_if(|partial| partial.received.len() >= partial.parts as usize)
+        {
+            self.snapshots.push_back(partial.snapshot);
+            if self.snapshots.len() > MAX_BUFFERED_SNAPSHOTS {
+                self.snapshots.pop_front();
+            }
+        }
+    }
+}
+
+/// Local mirror of a host entity.
+#[derive(Component)]
+struct Replica;
+
+#[derive(Resource, Default)]
+struct Replicas(HashMap<u64, Entity>);
+
+pub(super) fn build(app: &mut App, server: SocketAddr) -> NetRole {
+    let socket = UdpSocket::bind(("0.0.0.0", 0)).and_then(|socket| {
+        socket.connect(server)?;
+        socket.set_nonblocking(true)?;
+        Ok(socket)
+    });
+    let socket = match socket {
+        Ok(socket) => socket,
+        Err(error) => {
+            error!("Could not reach {server}: {error}, playing offline");
+            return NetRole::Offline;
+        }
+    };
+    info!("Joining {server}");
+
+    app.insert_resource(ClientSocket(socket))
+        .insert_resource(Connection {
+            player_number: None,
+            hello: Timer::from_seconds(HELLO_INTERVAL, TimerMode::Repeating),
+            last_received: 0.,
+        })
+        .init_resource::<SnapshotBuffer>()
+        .init_resource::<Replicas>()
+        .add_systems(OnEnter(AppState::InGame), reset_replication)
+        .add_systems(
+            PreUpdate,
+            receive_server_packets.run_if(in_state(AppState::InGame)),
+        )
+        .add_systems(
+            Update,
+            (
+                send_hello,
+                send_input,
+                forward_requests,
+                (
+                    sync_replicas,
+                    sync_world_state,
+                    interpolate_replicas,
+                    play_effects,
+                )
+                    .chain(),
+            )
+                .run_if(in_state(AppState::InGame)),
+        )
+        .add_systems(Last, say_goodbye);
+
+    NetRole::Client { server }
+}
+
+/// Replicas of the previous run were despawned with it, start over from the next snapshot.
+fn reset_replication(
+    mut buffer: ResMut<SnapshotBuffer>,
+    mut replicas: ResMut<Replicas>,
+    mut connection: ResMut<Connection>,
+    time: Res<Time<Real>>,
+) {
+    *buffer = SnapshotBuffer::default();
+    replicas.0.clear();
+    connection.last_received = time.elapsed_secs_f64();
+}
+
+fn send(socket: &ClientSocket, packet: &ClientPacket) {
+    if let Err(error) = socket.0.send(&packet.encode()) {
+        debug!("Could not send to host: {error}");
+    }
+}
+
+fn receive_server_packets(
+    socket: Res<ClientSocket>,
+    mut connection: ResMut<Connection>,
+    mut buffer: ResMut<SnapshotBuffer>,
+    mut next_state: ResMut<NextState<AppState>>,
+    mut stats: ResMut<RunStats>,
+    time: Res<Time<Real>>,
+    mut bytes: Local<Vec<u8>>,
+) {
+    let now = time.elapsed_secs_f64();
+    // Sized for the largest datagram once, then reused every frame.
+    bytes.resize(u16::MAX as usize, 0);
+
+    loop {
+        let len = match socket.0.recv(&mut bytes) {
+            Ok(len) => len,
+            Err(error) if error.kind() == ErrorKind::WouldBlock => break,
+            Err(error) => {
+                debug!("Could not receive from host: {error}");
+                break;
+            }
+        };
+        match ServerPacket::decode(&bytes[..len]) {
+            Some(ServerPacket::Welcome { player_number }) => {
+                if connection.player_number.is_none() {
+                    info!("Joined as P{player_number}");
+                }
+                connection.player_number = Some(player_number);
+                connection.last_received = now;
+            }
+            Some(ServerPacket::Snapshot {
+                part,
+                parts,
+                snapshot,
+            }) => {
+                connection.last_received = now;
+                buffer.receive(part, parts, snapshot);
+            }
+            Some(ServerPacket::GameOver(final_stats)) => {
+                // Repeats of a run we already left, until the host seats us again.
+                if connection.player_number.is_none() {
+                    continue;
+                }
+                info!("The host's CPU fell");
+                // The seat went with the run, a retry asks the host for a new one.
+                connection.player_number = None;
+                *stats = final_stats;
+                next_state.set(AppState::GameOver);
+            }
+            None => {}
+        }
+    }
+
+    if connection.player_number.is_some() && now - connection.last_received > HOST_TIMEOUT {
+        warn!("Lost connection to the host");
+        connection.player_number = None;
+        next_state.set(AppState::MainMenu);
+    }
+}
+
+fn send_hello(
+    socket: Res<ClientSocket>,
+    mut connection: ResMut<Connection>,
+    time: Res<Time<Real>>,
+) {
+    if connection.player_number.is_some() {
+        return;
+    }
+    if connection.hello.tick(time.delta()).just_finished() {
+        send(&socket, &ClientPacket::Hello);
+    }
+}
+
+/// Sends the input of the keyboard or first gamepad every frame, whichever was used last.
+///
+/// Losing a packet only loses that frame's presses.
+fn send_input(
+    socket: Res<ClientSocket>,
+    connection: Res<Connection>,
+    gamepads: Query<&Gamepad>,
+    keyboard: Res<ButtonInput<KeyCode>>,
+    last_device: Res<LastDevice>,
+) {
+    if connection.player_number.is_none() {
+        return;
+    }
+    let input = if last_device.0 == GlyphSet::Keyboard {
+        PlayerInput::from_keyboard(&keyboard)
+    } else {
+        gamepads
+            .iter()
+            .next()
+            .map(PlayerInput::from_gamepad)
+            .unwrap_or_default()
+    };
+    send(&socket, &ClientPacket::Input(NetInput::from(&input)));
+}
+
+fn forward_requests(socket: Res<ClientSocket>, mut requests: MessageReader<NetRequest>) {
+    for request in requests.read() {
+        let packet = match request {
+            NetRequest::Purchase(id) => ClientPacket::Purchase(UpgradeDef::index(*id) as u8),
+            NetRequest::StartWave => ClientPacket::StartWave,
+        };
+        send(&socket, &packet);
+    }
+}
+
+fn say_goodbye(
+    socket: Res<ClientSocket>,
+    connection: Res<Connection>,
+    mut exits: MessageReader<AppExit>,
+) {
+    if exits.read().next().is_some() && connection.player_number.is_some() {
+        send(&socket, &ClientPacket::Bye);
+    }
+}
+
+/// Assets replicas are drawn with, shared with the host-side spawners.
+#[derive(SystemParam)]
+struct ReplicaModels<'w> {
+    meshes: ResMut<'w, Assets<Mesh>>,
+    materials: ResMut<'w, Assets<StandardMaterial>>,
+    enemy: Res<'w, EnemyModel>,
+    bullet: Res<'w, BulletModel>,
+    pickups: Res<'w, PickupModels>,
+    power_ups: Res<'w, PowerUpModels>,
+}
+
+fn spawn_replica(
+    entity: &NetEntity,
+    commands: &mut Commands,
+    models: &mut ReplicaModels,
+) -> Entity {
+    let ReplicaModels {
+        meshes,
+        materials,
+        enemy,
+        bullet,
+        pickups,
+        power_ups,
+    } = models;
+    let transform = Transform::from_translation(entity.position)
+        .with_rotation(Quat::from_rotation_y(entity.yaw));
+    match &entity.kind {
+        NetKind::Player { number, .. } => {
+            let player = spawn_player(*number as usize, commands, meshes, materials);
+            commands.entity(player).insert((Replica, transform));
+            player
+        }
+        NetKind::Enemy(kind) => commands
+            .spawn((
+                DespawnOnExit(AppState::InGame),
+                Replica,
+                Mesh3d(enemy.meshes[kind].clone()),
+                MeshMaterial3d(enemy.materials[kind].clone()),
+                Model::new(ModelKind::Cockroach).scaled(kind.size()),
+                *kind,
+                transform,
+            ))
+            .id(),
+        NetKind::Bullet => commands
+            .spawn((
+                DespawnOnExit(AppState::InGame),
+                Replica,
+                bullet.mesh.clone(),
+                bullet.material.clone(),
+                transform,
+            ))
+            .id(),
+        NetKind::Coin => commands
+            .spawn((
+                DespawnOnExit(AppState::InGame),
+                Replica,
+                Mesh3d(pickups.coin_mesh.clone()),
+                MeshMaterial3d(pickups.coin_material.clone()),
+                transform,
+            ))
+            .id(),
+        NetKind::PowerUp(kind) => commands
+            .spawn((
+                DespawnOnExit(AppState::InGame),
+                Replica,
+                Mesh3d(power_ups.mesh.clone()),
+                MeshMaterial3d(power_ups.materials[kind].clone()),
+                transform,
+            ))
+            .id(),
+    }
+}
+
+/// Spawns and despawns replicas so they match the latest snapshot, and copies player data.
+fn sync_replicas(
+    buffer: Res<SnapshotBuffer>,
+    mut replicas: ResMut<Replicas>,
+    mut players: Query<&mut Player, With<Replica>>,
+    mut commands: Commands,
+    mut models: ReplicaModels,
+) {
+    if !buffer.is_changed() {
+        return;
+    }
+    let Some(latest) = buffer.snapshots.back() else {
+        return;
+    };
+
+    replicas.0.retain(|id, replica| {
+        let alive = latest.entities.iter().any(|entity| entity.id == *id);
+        if !alive {
+            commands.entity(*replica).despawn();
+        }
+        alive
+    });
+
+    for entity in &latest.entities {
+        let replica = *replicas
+            .0
+            .entry(entity.id)
+            .or_insert_with(|| spawn_replica(entity, &mut commands, &mut models));
+
+        if let NetKind::Player {
+            coins,
+            kills,
+            levels,
+            ..
+        } = &entity.kind
+            && let Ok(mut player) = players.get_mut(replica)
+        {
+            if player.coins != *coins {
+                player.coins = *coins;
+            }
+            if player.kills != *kills {
+                player.kills = *kills;
+            }
+            for (def, level) in UPGRADES.iter().zip(levels) {
+                if player.upgrades.level(def.id) != *level as u32 {
+                    player.upgrades.set_level(def.id, *level as u32);
+                }
+            }
+        }
+    }
+}
+
+/// Host state mirrored outside of replicas.
+#[derive(SystemParam)]
+struct WorldState<'w, 's> {
+    cpu: Single<'w, 's, &'static mut Health, With<Cpu>>,
+    wave: ResMut<'w, Wave>,
+    phase: Res<'w, State<GamePhase>>,
+    next_phase: ResMut<'w, NextState<GamePhase>>,
+}
+
+/// Mirrors CPU health, wave and phase, and binds the local gamepad to our own player.
+fn sync_world_state(
+    buffer: Res<SnapshotBuffer>,
+    connection: Res<Connection>,
+    mut world: WorldState,
+    players: Query<(Entity, &Player, Option<&PlayerGamepad>), With<Replica>>,
+    gamepads: Query<Entity, With<Gamepad>>,
+    mut commands: Commands,
+) {
+    if !buffer.is_changed() {
+        return;
+    }
+    let Some(latest) = buffer.snapshots.back() else {
+        return;
+    };
+
+    if world.cpu.0 != latest.cpu_health {
+        world.cpu.0 = latest.cpu_health;
+    }
+    if world.wave.number != latest.wave {
+        world.wave.number = latest.wave;
+    }
+    let host_phase = if latest.shop_open {
+        GamePhase::Shop
+    } else {
+        GamePhase::Combat
+    };
+    if *world.phase.get() != host_phase {
+        world.next_phase.set(host_phase);
+    }
+
+    // The shop picks its buyer by gamepad, so our seat has to know which one we hold.
+    if let Some(gamepad) = gamepads.iter().next() {
+        for (entity, player, bound) in &players {
+            let ours = connection.player_number == Some(player.number as u8);
+            if ours && bound.is_none_or(|bound| bound.0 != gamepad) {
+                commands.entity(entity).insert(PlayerGamepad(gamepad));
+            }
+        }
+    }
+}
+
+fn interpolate_replicas(
+    mut buffer: ResMut<SnapshotBuffer>,
+    replicas: Res<Replicas>,
+    mut transforms: Query<&mut Transform, With<Replica>>,
+    time: Res<Time<Real>>,
+) {
+    let Some(latest) = buffer.snapshots.back().map(|snapshot| snapshot.time) else {
+        return;
+    };
+    let clock = match buffer.bypass_change_detection().clock {
+        Some(clock) if (clock + time.delta_secs_f64() - latest).abs() <= MAX_CLOCK_DRIFT => {
+            clock + time.delta_secs_f64()
+        }
+        _ => latest,
+    };
+    buffer.bypass_change_detection().clock = Some(clock);
+    let render_time = clock - INTERPOLATION_DELAY;
+
+    let snapshots = &buffer.snapshots;
+    let after = snapshots
+        .iter()
+        .position(|snapshot| snapshot.time > render_time)
+        .unwrap_or(snapshots.len() - 1);
+    let before = after.saturating_sub(1);
+    let (from, to) = (&snapshots[before], &snapshots[after]);
+    let t = if to.time > from.time {
+        ((render_time - from.time) / (to.time - from.time)).clamp(0., 1.) as f32
+    } else {
+        1.
+    };
+
+    let from: HashMap<u64, &NetEntity> = from.entities.iter().map(|e| (e.id, e)).collect();
+    for target in &to.entities {
+        let Some(mut transform) = replicas
+            .0
+            .get(&target.id)
+            .and_then(|replica| transforms.get_mut(*replica).ok())
+        else {
+            continue;
+        };
+        let start = from.get(&target.id).copied().unwrap_or(target);
+        transform.translation = start.position.lerp(target.position, t);
+        transform.rotation =
+            Quat::from_rotation_y(start.yaw).slerp(Quat::from_rotation_y(target.yaw), t);
+    }
+}
+
+/// Plays the effects of the host once replicas are drawn at the time of their snapshot.
+fn play_effects(mut buffer: ResMut<SnapshotBuffer>, mut effects: MessageWriter<EmitEffect>) {
+    let Some(clock) = buffer.clock else {
+        return;
+    };
+    let render_time = clock - INTERPOLATION_DELAY;
+    let buffer = buffer.bypass_change_detection();
+    for snapshot in &buffer.snapshots {
+        if snapshot.time > buffer.played && snapshot.time <= render_time {
+            effects.write_batch(snapshot.effects.iter().cloned());
+            buffer.played = snapshot.time;
+        }
+    }
+}
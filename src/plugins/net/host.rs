@@ -0,0 +1,357 @@
+use std::{
+    io::ErrorKind,
+    net::{SocketAddr, UdpSocket},
+};
+
+use bevy::{ecs::system::SystemParam, prelude::*};
+
+use crate::{
+    AppState, Cpu,
+    plugins::{
+        enemy::{Enemy, EnemyKind},
+        gun::Bullet,
+        health::Health,
+        net::{
+            NetRole, SimulationSystems,
+            protocol::{ClientPacket, NetEntity, NetInput, NetKind, ServerPacket, Snapshot},
+        },
+        particles::EmitEffect,
+        pickup::{Pickup, PickupKind},
+        player::{Player, PlayerInput, ReadPlayerInput, free_player_number, spawn_player},
+        stats::RunStats,
+        upgrade::{UPGRADES, UpgradePurchased},
+        wave::{GamePhase, Wave},
+    },
+};
+
+const SNAPSHOT_RATE: f32 = 20.;
+/// Clients silent for longer than this are considered gone.
+const CLIENT_TIMEOUT: f64 = 5.;
+
+#[derive(Resource)]
+struct HostSocket(UdpSocket);
+
+#[derive(Resource)]
+struct SnapshotTimer(Timer);
+
+/// Clients seated in the current run, kept after it ends so they can be told about it.
+#[derive(Resource, Default)]
+struct Audience(Vec<SocketAddr>);
+
+/// Effects played since the last snapshot, so clients play them too.
+#[derive(Resource, Default)]
+struct PendingEffects(Vec<EmitEffect>);
+
+/// Player driven by a client over the network.
+#[derive(Component)]
+struct RemotePlayer {
+    address: SocketAddr,
+    last_seen: f64,
+    /// Input received since the last frame, button presses are kept until consumed.
+    pending: NetInput,
+}
+
+pub(super) fn build(app: &mut App, port: u16) -> NetRole {
+    let socket = UdpSocket::bind(("0.0.0.0", port))
+        .and_then(|socket| socket.set_nonblocking(true).map(|()| socket));
+    let socket = match socket {
+        Ok(socket) => socket,
+        Err(error) => {
+            error!("Could not host on port {port}: {error}, playing offline");
+            return NetRole::Offline;
+        }
+    };
+    info!("Hosting on port {port}");
+
+    app.insert_resource(HostSocket(socket))
+        .insert_resource(SnapshotTimer(Timer::from_seconds(
+            1. / SNAPSHOT_RATE,
+            TimerMode::Repeating,
+        )))
+        .init_resource::<Audience>()
+        .init_resource::<PendingEffects>()
+        .add_systems(
+            PreUpdate,
+            receive_client_packets.run_if(in_state(AppState::InGame)),
+        )
+        .add_systems(
+            Update,
+            (
+                apply_remote_input
+                    .in_set(ReadPlayerInput)
+                    .in_set(SimulationSystems),
+                drop_silent_clients,
+                remember_audience.run_if(in_state(AppState::InGame)),
+                send_game_over.run_if(in_state(AppState::GameOver)),
+            ),
+        )
+        .add_systems(
+            PostUpdate,
+            (collect_effects, send_snapshots)
+                .chain()
+                .run_if(in_state(AppState::InGame)),
+        );
+
+    NetRole::Host { port }
+}
+
+fn send(socket: &UdpSocket, address: SocketAddr, packet: &ServerPacket) {
+    if let Err(error) = socket.send_to(&packet.encode(), address) {
+        debug!("Could not send to {address}: {error}");
+    }
+}
+
+/// Shop actions clients ask the host to carry out.
+#[derive(SystemParam)]
+struct ShopRequests<'w> {
+    phase: Res<'w, State<GamePhase>>,
+    next_phase: ResMut<'w, NextState<GamePhase>>,
+    purchases: MessageWriter<'w, UpgradePurchased>,
+}
+
+fn receive_client_packets(
+    socket: Res<HostSocket>,
+    mut players: Query<(Entity, &mut Player, Option<&mut RemotePlayer>)>,
+    mut shop: ShopRequests,
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    time: Res<Time<Real>>,
+) {
+    let now = time.elapsed_secs_f64();
+    let mut taken: Vec<usize> = players.iter().map(|(_, player, _)| player.number).collect();
+    // Clients seated this frame, not visible to the query until commands are applied.
+    let mut joined: Vec<(SocketAddr, usize)> = Vec::new();
+    let mut buffer = [0; 1500];
+
+    loop {
+        let (len, address) = match socket.0.recv_from(&mut buffer) {
+            Ok(received) => received,
+            Err(error) if error.kind() == ErrorKind::WouldBlock => break,
+            Err(error) => {
+                debug!("Could not receive from clients: {error}");
+                break;
+            }
+        };
+        let Some(packet) = ClientPacket::decode(&buffer[..len]) else {
+            continue;
+        };
+
+        let client = players
+            .iter_mut()
+            .find(|(_, _, remote)| remote.as_ref().is_some_and(|r| r.address == address));
+        let Some((entity, mut player, Some(mut remote))) = client else {
+            if packet != ClientPacket::Hello {
+                continue;
+            }
+            let number = match joined.iter().find(|(joiner, _)| *joiner == address) {
+                Some((_, number)) => *number,
+                None => {
+                    let number = free_player_number(&taken);
+                    taken.push(number);
+                    joined.push((address, number));
+                    let player = spawn_player(number, &mut commands, &mut meshes, &mut materials);
+                    commands.entity(player).insert(RemotePlayer {
+                        address,
+                        last_seen: now,
+                        pending: NetInput::default(),
+                    });
+                    info!("{address} joined as P{number}");
+                    number
+                }
+            };
+            send(
+                &socket.0,
+                address,
+                &ServerPacket::Welcome {
+                    player_number: number as u8,
+                },
+            );
+            continue;
+        };
+
+        remote.last_seen = now;
+        match packet {
+            ClientPacket::Hello => send(
+                &socket.0,
+                address,
+                &ServerPacket::Welcome {
+                    player_number: player.number as u8,
+                },
+            ),
+            ClientPacket::Input(input) => {
+                let pending = &mut remote.pending;
+                pending.shoot |= input.shoot;
+                pending.purchase = pending.purchase.or(input.purchase);
+                pending.movement = input.movement;
+                pending.aim = input.aim;
+            }
+            ClientPacket::Purchase(index) => {
+                if let Some(def) = UPGRADES.get(index as usize)
+                    && let Ok(cost) = player.purchase(def)
+                {
+                    shop.purchases.write(UpgradePurchased {
+                        player: entity,
+                        id: def.id,
+                        cost,
+                    });
+                }
+            }
+            ClientPacket::StartWave => {
+                if *shop.phase.get() == GamePhase::Shop {
+                    shop.next_phase.set(GamePhase::Combat);
+                }
+            }
+            ClientPacket::Bye => {
+                info!("P{} left", player.number);
+                commands.entity(entity).despawn();
+            }
+        }
+    }
+}
+
+fn apply_remote_input(mut players: Query<(&mut RemotePlayer, &mut PlayerInput)>) {
+    for (mut remote, mut input) in &mut players {
+        *input = PlayerInput::from(&remote.pending);
+        remote.pending.shoot = false;
+        remote.pending.purchase = None;
+    }
+}
+
+fn drop_silent_clients(
+    players: Query<(Entity, &Player, &RemotePlayer)>,
+    time: Res<Time<Real>>,
+    mut commands: Commands,
+) {
+    let now = time.elapsed_secs_f64();
+    for (entity, player, remote) in &players {
+        if now - remote.last_seen > CLIENT_TIMEOUT {
+            info!("P{} timed out", player.number);
+            commands.entity(entity).despawn();
+        }
+    }
+}
+
+fn remember_audience(clients: Query<&RemotePlayer>, mut audience: ResMut<Audience>) {
+    audience.0 = clients.iter().map(|client| client.address).collect();
+}
+
+/// Repeats the end of the run for as long as the game-over screen is up, in case a datagram is lost.
+fn send_game_over(
+    socket: Res<HostSocket>,
+    mut timer: ResMut<SnapshotTimer>,
+    time: Res<Time<Real>>,
+    audience: Res<Audience>,
+    stats: Res<RunStats>,
+) {
+    if !timer.0.tick(time.delta()).just_finished() || audience.0.is_empty() {
+        return;
+    }
+    let packet = ServerPacket::GameOver(stats.clone());
+    for address in &audience.0 {
+        send(&socket.0, *address, &packet);
+    }
+}
+
+fn collect_effects(mut effects: MessageReader<EmitEffect>, mut pending: ResMut<PendingEffects>) {
+    pending.0.extend(effects.read().cloned());
+}
+
+fn yaw(transform: &Transform) -> f32 {
+    transform.rotation.to_euler(EulerRot::YXZ).0
+}
+
+/// Everything a snapshot is made of.
+#[derive(SystemParam)]
+struct Replicated<'w, 's> {
+    players: Query<'w, 's, (Entity, &'static Player, &'static Transform)>,
+    enemies: Query<'w, 's, (Entity, &'static EnemyKind, &'static Transform), With<Enemy>>,
+    bullets: Query<'w, 's, (Entity, &'static Transform), With<Bullet>>,
+    pickups: Query<'w, 's, (Entity, &'static Pickup, &'static Transform)>,
+    cpu: Single<'w, 's, &'static Health, With<Cpu>>,
+    wave: Res<'w, Wave>,
+    phase: Res<'w, State<GamePhase>>,
+}
+
+fn send_snapshots(
+    socket: Res<HostSocket>,
+    mut timer: ResMut<SnapshotTimer>,
+    time: Res<Time<Real>>,
+    clients: Query<&RemotePlayer>,
+    world: Replicated,
+    mut effects: ResMut<PendingEffects>,
+) {
+    if clients.is_empty() {
+        effects.0.clear();
+        return;
+    }
+    if !timer.0.tick(time.delta()).just_finished() {
+        return;
+    }
+    let Replicated {
+        players,
+        enemies,
+        bullets,
+        pickups,
+        cpu,
+        wave,
+        phase,
+    } = world;
+
+    let players = players.iter().map(|(entity, player, transform)| NetEntity {
+        id: entity.to_bits(),
+        kind: NetKind::Player {
+            number: player.number as u8,
+            coins: player.coins,
+            kills: player.kills,
+            levels: UPGRADES
+                .iter()
+                .map(|def| player.upgrades.level(def.id) as u8)
+                .collect(),
+        },
+        position: transform.translation,
+        yaw: yaw(transform),
+    });
+    let simple = |kind: NetKind| {
+        move |(entity, transform): (Entity, &Transform)| NetEntity {
+            id: entity.to_bits(),
+            kind: kind.clone(),
+            position: transform.translation,
+            yaw: yaw(transform),
+        }
+    };
+    let enemies = enemies.iter().map(|(entity, kind, transform)| NetEntity {
+        id: entity.to_bits(),
+        kind: NetKind::Enemy(*kind),
+        position: transform.translation,
+        yaw: yaw(transform),
+    });
+    let pickups = pickups.iter().map(|(entity, pickup, transform)| NetEntity {
+        id: entity.to_bits(),
+        kind: match pickup.kind {
+            PickupKind::Coins(_) => NetKind::Coin,
+            PickupKind::PowerUp(kind) => NetKind::PowerUp(kind),
+        },
+        position: transform.translation,
+        yaw: yaw(transform),
+    });
+
+    let packets = Snapshot {
+        time: time.elapsed_secs_f64(),
+        shop_open: *phase.get() == GamePhase::Shop,
+        wave: wave.number,
+        cpu_health: cpu.0,
+        entities: players
+            .chain(enemies)
+            .chain(bullets.iter().map(simple(NetKind::Bullet)))
+            .chain(pickups)
+            .collect(),
+        effects: std::mem::take(&mut effects.0),
+    }
+    .into_packets();
+    for client in &clients {
+        for packet in &packets {
+            send(&socket.0, client.address, packet);
+        }
+    }
+}
@@ -0,0 +1,108 @@
+//! LAN play: one instance hosts the authoritative simulation and clients mirror it.
+//!
+//! Start a host with `--host [port]` and join it with `--join <address[:port]>`.
+//! Without either flag the game runs offline, exactly like a host nobody joined.
+
+use std::net::{SocketAddr, ToSocketAddrs};
+
+use bevy::prelude::*;
+
+use crate::plugins::upgrade::UpgradeId;
+
+mod client;
+mod host;
+mod protocol;
+
+pub const DEFAULT_PORT: u16 = 7777;
+
+#[derive(Resource, Clone, Debug, PartialEq, Eq)]
+pub enum NetRole {
+    Offline,
+    Host { port: u16 },
+    Client { server: SocketAddr },
+}
+
+impl NetRole {
+    fn from_args(args: impl Iterator<Item = String>) -> Self {
+        let mut args = args.peekable();
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--host" => {
+                    // The port is optional, the next argument may be another flag.
+                    let port = args
+                        .next_if(|value| value.parse::<u16>().is_ok())
+                        .and_then(|port| port.parse().ok());
+                    if port.is_none()
+                        && let Some(value) = args.peek().filter(|value| !value.starts_with("--"))
+                    {
+                        warn!("Invalid port {value}, hosting on port {DEFAULT_PORT}");
+                    }
+                    return NetRole::Host {
+                        port: port.unwrap_or(DEFAULT_PORT),
+                    };
+                }
+                "--join" => {
+                    let Some(address) = args.next() else {
+                        warn!("--join expects an address, playing offline");
+                        return NetRole::Offline;
+                    };
+                    let address = if address.contains(':') {
+                        address
+                    } else {
+                        format!("{address}:{DEFAULT_PORT}")
+                    };
+                    let server = address
+                        .to_socket_addrs()
+                        .ok()
+                        .and_then(|mut addresses| addresses.find(SocketAddr::is_ipv4));
+                    return match server {
+                        Some(server) => NetRole::Client { server },
+                        None => {
+                            warn!("Could not resolve {address}, playing offline");
+                            NetRole::Offline
+                        }
+                    };
+                }
+                _ => {}
+            }
+        }
+        NetRole::Offline
+    }
+
+    pub fn is_client(&self) -> bool {
+        matches!(self, NetRole::Client { .. })
+    }
+}
+
+/// Gameplay systems only the authoritative instance runs, clients get their results from snapshots.
+#[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct SimulationSystems;
+
+pub fn is_authority(role: Res<NetRole>) -> bool {
+    !role.is_client()
+}
+
+/// Shop actions a client forwards to the host instead of applying them itself.
+#[derive(Message, Debug, Clone, Copy)]
+pub enum NetRequest {
+    Purchase(UpgradeId),
+    StartWave,
+}
+
+pub struct NetPlugin;
+
+impl Plugin for NetPlugin {
+    fn build(&self, app: &mut App) {
+        let role = NetRole::from_args(std::env::args().skip(1));
+
+        app.add_message::<NetRequest>()
+            .configure_sets(Update, SimulationSystems.run_if(is_authority));
+
+        let role = match role {
+            NetRole::Host { port } => host::build(app, port),
+            NetRole::Client { server } => client::build(app, server),
+            NetRole::Offline => NetRole::Offline,
+        };
+        app.insert_resource(role);
+    }
+}
@@ -0,0 +1,706 @@
+//! Binary wire format shared by the host and its clients.
+//!
+//! Every datagram starts with [`MAGIC`] and [`PROTOCOL_VERSION`], so packets from
+//! another game or another build are dropped instead of misread. Numbers are little-endian.
+//! Snapshots are split so that no datagram is larger than [`MAX_PACKET_SIZE`].
+
+use std::collections::HashMap;
+
+use bevy::prelude::*;
+
+use crate::plugins::{
+    enemy::{ENEMY_KINDS, EnemyKind},
+    particles::{EFFECTS, EmitEffect},
+    player::PlayerInput,
+    powerup::{POWER_UPS, PowerUpKind},
+    stats::RunStats,
+    upgrade::{UPGRADES, UpgradeDef},
+};
+
+const MAGIC: &[u8; 3] = b"IMR";
+const PROTOCOL_VERSION: u8 = 5;
+/// Below the path MTU of common links, so that datagrams are never fragmented by IP.
+pub const MAX_PACKET_SIZE: usize = 1200;
+
+/// Packets sent by a client to the host.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ClientPacket {
+    /// Asks for a seat, repeated until the host answers with [`ServerPacket::Welcome`].
+    Hello,
+    Input(NetInput),
+    /// Buys an upgrade from the shop, by index in [`UPGRADES`].
+    Purchase(u8),
+    StartWave,
+    Bye,
+}
+
+/// Packets sent by the host to its clients.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ServerPacket {
+    Welcome {
+        player_number: u8,
+    },
+    /// Part `part` of the `parts` a snapshot was split into.
+    Snapshot {
+        part: u8,
+        parts: u8,
+        snapshot: Snapshot,
+    },
+    /// The CPU fell, with the stats of the run that ended.
+    GameOver(RunStats),
+}
+
+/// [`PlayerInput`] as sent over the network.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct NetInput {
+    pub movement: Vec2,
+    pub aim: Vec2,
+    pub shoot: bool,
+    /// Index in [`UPGRADES`] of the upgrade bought with a face button.
+    pub purchase: Option<u8>,
+}
+
+impl From<&PlayerInput> for NetInput {
+    fn from(input: &PlayerInput) -> Self {
+        Self {
+            movement: input.movement,
+            aim: input.aim,
+            shoot: input.shoot,
+            purchase: input.purchase.map(|id| UpgradeDef::index(id) as u8),
+        }
+    }
+}
+
+impl From<&NetInput> for PlayerInput {
+    fn from(input: &NetInput) -> Self {
+        Self {
+            movement: input.movement,
+            aim: input.aim,
+            shoot: input.shoot,
+            purchase: input
+                .purchase
+                .and_then(|index| UPGRADES.get(index as usize))
+                .map(|def| def.id),
+        }
+    }
+}
+
+/// State of the simulation at host time `time`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Snapshot {
+    pub time: f64,
+    pub shop_open: bool,
+    pub wave: u32,
+    pub cpu_health: f32,
+    pub entities: Vec<NetEntity>,
+    /// Effects played by the host since the previous snapshot.
+    pub effects: Vec<EmitEffect>,
+}
+
+impl Snapshot {
+    /// Splits the snapshot into packets of at most [`MAX_PACKET_SIZE`] bytes.
+    ///
+    /// Beyond `u8::MAX` parts, the remaining entities and effects are left out.
+    pub fn into_packets(self) -> Vec<ServerPacket> {
+        let Snapshot {
+            time,
+            shop_open,
+            wave,
+            cpu_health,
+            entities,
+            effects,
+        } = self;
+        let empty = || Snapshot {
+            time,
+            shop_open,
+            wave,
+            cpu_health,
+            entities: Vec::new(),
+            effects: Vec::new(),
+        };
+        let header = ServerPacket::Snapshot {
+            part: 0,
+            parts: 1,
+            snapshot: empty(),
+        }
+        .encode()
+        .len();
+
+        let mut parts = vec![empty()];
+        let mut len = header;
+        let mut make_room = |size: usize, parts: &mut Vec<Snapshot>| {
+            if len + size > MAX_PACKET_SIZE {
+                parts.push(empty());
+                len = header;
+            }
+            len += size;
+        };
+        for entity in entities {
+            make_room(Writer::measure(|writer| writer.entity(&entity)), &mut parts);
+            parts.last_mut().unwrap().entities.push(entity);
+        }
+        for effect in effects {
+            make_room(Writer::measure(|writer| writer.effect(&effect)), &mut parts);
+            parts.last_mut().unwrap().effects.push(effect);
+        }
+        parts.truncate(u8::MAX as usize);
+
+        let count = parts.len() as u8;
+        parts
+            .into_iter()
+            .enumerate()
+            .map(|(part, snapshot)| ServerPacket::Snapshot {
+                part: part as u8,
+                parts: count,
+                snapshot,
+            })
+            .collect()
+    }
+
+    /// Adds the entities and effects of another part of the same snapshot.
+    pub fn merge(&mut self, part: Snapshot) {
+        self.entities.extend(part.entities);
+        self.effects.extend(part.effects);
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct NetEntity {
+    /// Host-side entity bits, stable for as long as the entity lives.
+    pub id: u64,
+    pub kind: NetKind,
+    pub position: Vec3,
+    pub yaw: f32,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum NetKind {
+    Player {
+        number: u8,
+        coins: u32,
+        kills: u32,
+        /// Level of every upgrade, in [`UPGRADES`] order.
+        levels: Vec<u8>,
+    },
+    Enemy(EnemyKind),
+    Bullet,
+    Coin,
+    PowerUp(PowerUpKind),
+}
+
+impl ClientPacket {
+    pub fn encode(&self) -> Vec<u8> {
+        let mut writer = Writer::new();
+        match self {
+            ClientPacket::Hello => writer.u8(0),
+            ClientPacket::Input(input) => {
+                writer.u8(1);
+                writer.vec2(input.movement);
+                writer.vec2(input.aim);
+                writer.u8(input.shoot as u8);
+                writer.u8(input.purchase.map_or(u8::MAX, |index| index));
+            }
+            ClientPacket::Purchase(index) => {
+                writer.u8(2);
+                writer.u8(*index);
+            }
+            ClientPacket::StartWave => writer.u8(3),
+            ClientPacket::Bye => writer.u8(4),
+        }
+        writer.0
+    }
+
+    pub fn decode(bytes: &[u8]) -> Option<Self> {
+        let mut reader = Reader::new(bytes)?;
+        let packet = match reader.u8()? {
+            0 => ClientPacket::Hello,
+            1 => ClientPacket::Input(NetInput {
+                movement: reader.vec2()?,
+                aim: reader.vec2()?,
+                shoot: reader.u8()? != 0,
+                purchase: Some(reader.u8()?).filter(|index| *index != u8::MAX),
+            }),
+            2 => ClientPacket::Purchase(reader.u8()?),
+            3 => ClientPacket::StartWave,
+            4 => ClientPacket::Bye,
+            _ => return None,
+        };
+        Some(packet)
+    }
+}
+
+impl ServerPacket {
+    pub fn encode(&self) -> Vec<u8> {
+        let mut writer = Writer::new();
+        match self {
+            ServerPacket::Welcome { player_number } => {
+                writer.u8(0);
+                writer.u8(*player_number);
+            }
+            ServerPacket::Snapshot {
+                part,
+                parts,
+                snapshot,
+            } => {
+                writer.u8(1);
+                writer.u8(*part);
+                writer.u8(*parts);
+                writer.f64(snapshot.time);
+                writer.u8(snapshot.shop_open as u8);
+                writer.u32(snapshot.wave);
+                writer.f32(snapshot.cpu_health);
+                writer.u32(snapshot.entities.len() as u32);
+                for entity in &snapshot.entities {
+                    writer.entity(entity);
+                }
+                writer.u32(snapshot.effects.len() as u32);
+                for effect in &snapshot.effects {
+                    writer.effect(effect);
+                }
+            }
+            ServerPacket::GameOver(stats) => {
+                writer.u8(2);
+                writer.u32(stats.waves_cleared);
+                writer.u32(stats.kills);
+                for kind in ENEMY_KINDS {
+                    writer.u32(stats.kills_by_kind.get(kind).copied().unwrap_or_default());
+                }
+                writer.u32(stats.shots_fired);
+                writer.u32(stats.hits);
+                writer.f32(stats.damage_dealt);
+                writer.u32(stats.coins_earned);
+                writer.u32(stats.coins_spent);
+                writer.u32(stats.upgrades_bought);
+                writer.f32(stats.time_alive);
+            }
+        }
+        writer.0
+    }
+
+    pub fn decode(bytes: &[u8]) -> Option<Self> {
+        let mut reader = Reader::new(bytes)?;
+        let packet = match reader.u8()? {
+            0 => ServerPacket::Welcome {
+                player_number: reader.u8()?,
+            },
+            1 => {
+                let part = reader.u8()?;
+                let parts = reader.u8()?;
+                let time = reader.f64()?;
+                let shop_open = reader.u8()? != 0;
+                let wave = reader.u32()?;
+                let cpu_health = reader.f32()?;
+                let count = reader.u32()?;
+                let entities = (0..count).map(|_| reader.entity()).collect::<Option<_>>()?;
+                let count = reader.u32()?;
+                let effects = (0..count).map(|_| reader.effect()).collect::<Option<_>>()?;
+                ServerPacket::Snapshot {
+                    part,
+                    parts,
+                    snapshot: Snapshot {
+                        time,
+                        shop_open,
+                        wave,
+                        cpu_health,
+                        entities,
+                        effects,
+                    },
+                }
+            }
+            2 => {
+                let waves_cleared = reader.u32()?;
+                let kills = reader.u32()?;
+                let mut kills_by_kind = HashMap::new();
+                for kind in ENEMY_KINDS {
+                    let count = reader.u32()?;
+                    if count > 0 {
+                        kills_by_kind.insert(*kind, count);
+                    }
+                }
+                ServerPacket::GameOver(RunStats {
+                    waves_cleared,
+                    kills,
+                    kills_by_kind,
+                    shots_fired: reader.u32()?,
+                    hits: reader.u32()?,
+                    damage_dealt: reader.f32()?,
+                    coins_earned: reader.u32()?,
+                    coins_spent: reader.u32()?,
+                    upgrades_bought: reader.u32()?,
+                    time_alive: reader.f32()?,
+                })
+            }
+            _ => return None,
+        };
+        Some(packet)
+    }
+}
+
+struct Writer(Vec<u8>);
+
+impl Writer {
+    fn new() -> Self {
+        let mut bytes = MAGIC.to_vec();
+        bytes.push(PROTOCOL_VERSION);
+        Self(bytes)
+    }
+
+    fn u8(&mut self, value: u8) {
+        self.0.push(value);
+    }
+
+    fn u32(&mut self, value: u32) {
+        self.0.extend_from_slice(&value.to_le_bytes());
+    }
+
+    fn u64(&mut self, value: u64) {
+        self.0.extend_from_slice(&value.to_le_bytes());
+    }
+
+    fn f32(&mut self, value: f32) {
+        self.0.extend_from_slice(&value.to_le_bytes());
+    }
+
+    fn f64(&mut self, value: f64) {
+        self.0.extend_from_slice(&value.to_le_bytes());
+    }
+
+    fn vec2(&mut self, value: Vec2) {
+        self.f32(value.x);
+        self.f32(value.y);
+    }
+
+    fn vec3(&mut self, value: Vec3) {
+        self.f32(value.x);
+        self.f32(value.y);
+        self.f32(value.z);
+    }
+
+    /// Number of bytes `write` adds.
+    fn measure(write: impl FnOnce(&mut Writer)) -> usize {
+        let mut writer = Writer(Vec::new());
+        write(&mut writer);
+        writer.0.len()
+    }
+
+    fn entity(&mut self, entity: &NetEntity) {
+        self.u64(entity.id);
+        self.vec3(entity.position);
+        self.f32(entity.yaw);
+        match &entity.kind {
+            NetKind::Player {
+                number,
+                coins,
+                kills,
+                levels,
+            } => {
+                self.u8(0);
+                self.u8(*number);
+                self.u32(*coins);
+                self.u32(*kills);
+                self.u8(levels.len() as u8);
+                for level in levels {
+                    self.u8(*level);
+                }
+            }
+            NetKind::Enemy(kind) => {
+                self.u8(1);
+                self.u8(ENEMY_KINDS.iter().position(|k| k == kind).unwrap() as u8);
+            }
+            NetKind::Bullet => self.u8(2),
+            NetKind::Coin => self.u8(3),
+            NetKind::PowerUp(kind) => {
+                self.u8(4);
+                self.u8(POWER_UPS.iter().position(|k| k == kind).unwrap() as u8);
+            }
+        }
+    }
+
+    fn effect(&mut self, effect: &EmitEffect) {
+        self.u8(EFFECTS.iter().position(|e| *e == effect.effect).unwrap() as u8);
+        self.vec3(effect.position);
+        match effect.direction {
+            Some(direction) => {
+                self.u8(1);
+                self.vec3(direction);
+            }
+            None => self.u8(0),
+        }
+    }
+}
+
+struct Reader<'a>(&'a [u8]);
+
+impl<'a> Reader<'a> {
+    /// Checks the header, returning `None` for foreign or outdated packets.
+    fn new(bytes: &'a [u8]) -> Option<Self> {
+        let rest = bytes.strip_prefix(MAGIC.as_slice())?;
+        let (version, rest) = rest.split_first()?;
+        (*version == PROTOCOL_VERSION).then_some(Self(rest))
+    }
+
+    fn bytes<const N: usize>(&mut self) -> Option<[u8; N]> {
+        let (bytes, rest) = self.0.split_first_chunk::<N>()?;
+        self.0 = rest;
+        Some(*bytes)
+    }
+
+    fn u8(&mut self) -> Option<u8> {
+        self.bytes::<1>().map(|[value]| value)
+    }
+
+    fn u32(&mut self) -> Option<u32> {
+        self.bytes().map(u32::from_le_bytes)
+    }
+
+    fn u64(&mut self) -> Option<u64> {
+        self.bytes().map(u64::from_le_bytes)
+    }
+
+    fn f32(&mut self) -> Option<f32> {
+        self.bytes().map(f32::from_le_bytes)
+    }
+
+    fn f64(&mut self) -> Option<f64> {
+        self.bytes().map(f64::from_le_bytes)
+    }
+
+    fn vec2(&mut self) -> Option<Vec2> {
+        Some(Vec2::new(self.f32()?, self.f32()?))
+    }
+
+    fn vec3(&mut self) -> Option<Vec3> {
+        Some(Vec3::new(self.f32()?, self.f32()?, self.f32()?))
+    }
+
+    fn entity(&mut self) -> Option<NetEntity> {
+        let id = self.u64()?;
+        let position = self.vec3()?;
+        let yaw = self.f32()?;
+        let kind = match self.u8()? {
+            0 => {
+                let number = self.u8()?;
+                let coins = self.u32()?;
+                let kills = self.u32()?;
+                let len = self.u8()?;
+                let levels = (0..len).map(|_| self.u8()).collect::<Option<_>>()?;
+                NetKind::Player {
+                    number,
+                    coins,
+                    kills,
+                    levels,
+                }
+            }
+            1 => NetKind::Enemy(*ENEMY_KINDS.get(self.u8()? as usize)?),
+            2 => NetKind::Bullet,
+            3 => NetKind::Coin,
+            4 => NetKind::PowerUp(*POWER_UPS.get(self.u8()? as usize)?),
+            _ => return None,
+        };
+        Some(NetEntity {
+            id,
+            kind,
+            position,
+            yaw,
+        })
+    }
+
+    fn effect(&mut self) -> Option<EmitEffect> {
+        let effect = *EFFECTS.get(self.u8()? as usize)?;
+        let position = self.vec3()?;
+        let direction = match self.u8()? {
+            0 => None,
+            1 => Some(self.vec3()?),
+            _ => return None,
+        };
+        Some(EmitEffect {
+            effect,
+            position,
+            direction,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::plugins::particles::Effect;
+
+    #[test]
+    fn client_packets_round_trip() {
+        let packets = [
+            ClientPacket::Hello,
+            ClientPacket::Input(NetInput {
+                movement: Vec2::new(0.5, -1.),
+                aim: Vec2::new(-0.25, 0.75),
+                shoot: true,
+                purchase: Some(3),
+            }),
+            ClientPacket::Input(NetInput::default()),
+            ClientPacket::Purchase(2),
+            ClientPacket::StartWave,
+            ClientPacket::Bye,
+        ];
+        for packet in packets {
+            assert_eq!(ClientPacket::decode(&packet.encode()), Some(packet));
+        }
+    }
+
+    #[test]
+    fn server_packets_round_trip() {
+        let entity = |id, kind| NetEntity {
+            id,
+            kind,
+            position: Vec3::new(id as f32, 0.5, -2.),
+            yaw: 1.25,
+        };
+        let packets = [
+            ServerPacket::Welcome { player_number: 3 },
+            ServerPacket::Snapshot {
+                part: 1,
+                parts: 2,
+                snapshot: Snapshot {
+                    time: 12.5,
+                    shop_open: true,
+                    wave: 4,
+                    cpu_health: 87.5,
+                    entities: vec![
+                        entity(
+                            1,
+                            NetKind::Player {
+                                number: 2,
+                                coins: 140,
+                                kills: 17,
+                                levels: vec![1, 0, 3],
+                            },
+                        ),
+                        entity(2, NetKind::Enemy(EnemyKind::Tank)),
+                        entity(3, NetKind::Bullet),
+                        entity(4, NetKind::Coin),
+                        entity(5, NetKind::PowerUp(PowerUpKind::Shield)),
+                    ],
+                    effects: vec![
+                        EmitEffect {
+                            effect: Effect::MuzzleFlash,
+                            position: Vec3::new(1., 0.5, 2.),
+                            direction: Some(Vec3::X),
+                        },
+                        EmitEffect {
+                            effect: Effect::Death,
+                            position: Vec3::new(-3., 0.5, 0.),
+                            direction: None,
+                        },
+                    ],
+                },
+            },
+            ServerPacket::GameOver(RunStats {
+                waves_cleared: 6,
+                kills: 48,
+                kills_by_kind: HashMap::from([(EnemyKind::Grunt, 40), (EnemyKind::Tank, 8)]),
+                shots_fired: 300,
+                hits: 120,
+                damage_dealt: 5400.,
+                coins_earned: 350,
+                coins_spent: 280,
+                upgrades_bought: 7,
+                time_alive: 412.5,
+            }),
+            ServerPacket::Snapshot {
+                part: 0,
+                parts: 1,
+                snapshot: Snapshot {
+                    time: 0.,
+                    shop_open: false,
+                    wave: 1,
+                    cpu_health: 100.,
+                    entities: Vec::new(),
+                    effects: Vec::new(),
+                },
+            },
+        ];
+        for packet in packets {
+            assert_eq!(ServerPacket::decode(&packet.encode()), Some(packet));
+        }
+    }
+
+    #[test]
+    fn crowded_snapshots_are_split_under_the_packet_size() {
+        let mut entities: Vec<_> = (0..8)
+            .map(|number| NetEntity {
+                id: number,
+                kind: NetKind::Player {
+                    number: number as u8 + 1,
+                    coins: u32::MAX,
+                    kills: u32::MAX,
+                    levels: vec![u8::MAX; UPGRADES.len()],
+                },
+                position: Vec3::ONE,
+                yaw: 0.5,
+            })
+            .collect();
+        let kinds = [
+            NetKind::Enemy(EnemyKind::Tank),
+            NetKind::Bullet,
+            NetKind::Coin,
+            NetKind::PowerUp(PowerUpKind::Bomb),
+        ];
+        for id in 8..5000 {
+            entities.push(NetEntity {
+                id,
+                kind: kinds[id as usize % kinds.len()].clone(),
+                position: Vec3::new(id as f32, 0.5, -(id as f32)),
+                yaw: -1.,
+            });
+        }
+        let effects: Vec<_> = (0..1000)
+            .map(|index| EmitEffect {
+                effect: Effect::MuzzleFlash,
+                position: Vec3::splat(index as f32),
+                direction: Some(Vec3::X),
+            })
+            .collect();
+        let snapshot = Snapshot {
+            time: 600.,
+            shop_open: false,
+            wave: 99,
+            cpu_health: 1.,
+            entities,
+            effects,
+        };
+
+        let packets = snapshot.clone().into_packets();
+        assert!(packets.len() > 1);
+        let mut merged: Option<Snapshot> = None;
+        for (index, packet) in packets.iter().enumerate() {
+            let bytes = packet.encode();
+            assert!(
+                bytes.len() <= MAX_PACKET_SIZE,
+                "part {index} is {} bytes",
+                bytes.len()
+            );
+            let Some(ServerPacket::Snapshot {
+                part,
+                parts,
+                snapshot: part_snapshot,
+            }) = ServerPacket::decode(&bytes)
+            else {
+                panic!("part {index} did not decode");
+            };
+            assert_eq!((part as usize, parts as usize), (index, packets.len()));
+            match &mut merged {
+                Some(merged) => merged.merge(part_snapshot),
+                None => merged = Some(part_snapshot),
+            }
+        }
+        assert_eq!(merged, Some(snapshot));
+    }
+
+    #[test]
+    fn foreign_and_truncated_packets_are_dropped() {
+        let mut bytes = ClientPacket::Purchase(1).encode();
+        assert_eq!(ClientPacket::decode(&bytes[..bytes.len() - 1]), None);
+        bytes[MAGIC.len()] = PROTOCOL_VERSION + 1;
+        assert_eq!(ClientPacket::decode(&bytes), None);
+        assert_eq!(ServerPacket::decode(b"not a packet"), None);
+    }
+}
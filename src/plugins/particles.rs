@@ -0,0 +1,287 @@
+use std::collections::HashMap;
+
+use bevy::{
+    asset::{AssetLoader, LoadContext, io::Reader},
+    prelude::*,
+};
+use rand::Rng;
+use serde::Deserialize;
+
+use crate::plugins::{camera::GameCamera, enemy::EnemyKilled, gun::GunShootEvent};
+
+pub struct ParticlePlugin;
+
+const EFFECTS_PATH: &str = "particles.effects.ron";
+/// Particles alive at once, emitting more while the pool is empty does nothing.
+const POOL_SIZE: usize = 512;
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum Effect {
+    /// A bullet hitting something.
+    Spark,
+    Death,
+    MuzzleFlash,
+}
+
+impl Effect {
+    /// Name of the effect in [`EFFECTS_PATH`].
+    fn key(self) -> &'static str {
+        match self {
+            Effect::Spark => "spark",
+            Effect::Death => "death",
+            Effect::MuzzleFlash => "muzzle",
+        }
+    }
+}
+
+pub const EFFECTS: &[Effect] = &[Effect::Spark, Effect::Death, Effect::MuzzleFlash];
+
+/// Asks for `effect` to be played at `position`, thrown towards `direction` if given.
+///
+/// The host forwards every request to its clients with the next snapshot.
+#[derive(Message, Clone, PartialEq, Debug)]
+pub struct EmitEffect {
+    pub effect: Effect,
+    pub position: Vec3,
+    pub direction: Option<Vec3>,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+struct EffectDef {
+    count: u32,
+    lifetime: (f32, f32),
+    speed: (f32, f32),
+    /// Half-angle of the emission cone, in degrees.
+    spread: f32,
+    size: (f32, f32),
+    color: (f32, f32, f32),
+    gravity: f32,
+}
+
+#[derive(Asset, TypePath, Deserialize, Debug)]
+struct EffectLibrary {
+    effects: HashMap<String, EffectDef>,
+}
+
+#[derive(Default, TypePath)]
+struct EffectLibraryLoader;
+
+impl AssetLoader for EffectLibraryLoader {
+    type Asset = EffectLibrary;
+    type Settings = ();
+    type Error = BevyError;
+
+    async fn load(
+        &self,
+        reader: &mut dyn Reader,
+        _settings: &(),
+        _load_context: &mut LoadContext<'_>,
+    ) -> Result<EffectLibrary, BevyError> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).await?;
+        Ok(ron::de::from_bytes(&bytes)?)
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["effects.ron"]
+    }
+}
+
+#[derive(Resource)]
+struct Effects(Handle<EffectLibrary>);
+
+/// One material per effect, shared by all of its particles so that none is re-uploaded as they fade.
+#[derive(Resource, Default)]
+struct EffectMaterials(HashMap<Effect, Handle<StandardMaterial>>);
+
+/// A pooled quad; hidden and listed in [`ParticlePool`] while unused.
+#[derive(Component, Default)]
+struct Particle {
+    velocity: Vec3,
+    age: f32,
+    lifetime: f32,
+    size: (f32, f32),
+    gravity: f32,
+}
+
+#[derive(Resource, Default)]
+struct ParticlePool {
+    free: Vec<Entity>,
+}
+
+impl Plugin for ParticlePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_asset::<EffectLibrary>()
+            .init_asset_loader::<EffectLibraryLoader>()
+            .init_resource::<ParticlePool>()
+            .init_resource::<EffectMaterials>()
+            .add_message::<EmitEffect>()
+            .add_systems(Startup, (load_effects, spawn_particle_pool))
+            .add_systems(
+                Update,
+                (emit_gameplay_effects, emit_particles, update_particles).chain(),
+            );
+    }
+}
+
+fn load_effects(asset_server: Res<AssetServer>, mut commands: Commands) {
+    commands.insert_resource(Effects(asset_server.load(EFFECTS_PATH)));
+}
+
+fn spawn_particle_pool(
+    mut commands: Commands,
+    mut pool: ResMut<ParticlePool>,
+    mut meshes: ResMut<Assets<Mesh>>,
+) {
+    let quad = meshes.add(Rectangle::new(1., 1.));
+    for _ in 0..POOL_SIZE {
+        let particle = commands
+            .spawn((
+                Particle::default(),
+                Mesh3d(quad.clone()),
+                // Given the material of its effect when emitted.
+                MeshMaterial3d::<StandardMaterial>::default(),
+                Transform::default(),
+                Visibility::Hidden,
+            ))
+            .id();
+        pool.free.push(particle);
+    }
+}
+
+/// Effects following from gameplay messages, so the systems sending them need not know.
+fn emit_gameplay_effects(
+    mut shots: MessageReader<GunShootEvent>,
+    mut kills: MessageReader<EnemyKilled>,
+    mut effects: MessageWriter<EmitEffect>,
+) {
+    for shot in shots.read() {
+        effects.write(EmitEffect {
+            effect: Effect::MuzzleFlash,
+            position: shot.source,
+            direction: Some(shot.target - shot.source),
+        });
+    }
+    for kill in kills.read() {
+        effects.write(EmitEffect {
+            effect: Effect::Death,
+            position: kill.position,
+            direction: None,
+        });
+    }
+}
+
+/// Random direction at most `spread` degrees away from `axis`.
+fn cone_direction(axis: Vec3, spread: f32, rng: &mut impl Rng) -> Vec3 {
+    let spread = spread.to_radians().clamp(0., std::f32::consts::PI);
+    let cos = rng.random_range(spread.cos()..=1.);
+    let sin = (1. - cos * cos).sqrt();
+    let angle = rng.random_range(0. ..std::f32::consts::TAU);
+    let local = Vec3::new(sin * angle.cos(), cos, sin * angle.sin());
+    Quat::from_rotation_arc(Vec3::Y, axis.normalize_or(Vec3::Y)) * local
+}
+
+fn range(rng: &mut impl Rng, (min, max): (f32, f32)) -> f32 {
+    if min < max {
+        rng.random_range(min..=max)
+    } else {
+        min
+    }
+}
+
+type PooledParticles<'w, 's> = Query<
+    'w,
+    's,
+    (
+        &'static mut Particle,
+        &'static mut Transform,
+        &'static mut Visibility,
+        &'static mut MeshMaterial3d<StandardMaterial>,
+    ),
+>;
+
+fn emit_particles(
+    mut requests: MessageReader<EmitEffect>,
+    effects: Res<Effects>,
+    libraries: Res<Assets<EffectLibrary>>,
+    mut effect_materials: ResMut<EffectMaterials>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut pool: ResMut<ParticlePool>,
+    mut particles: PooledParticles,
+) {
+    let Some(library) = libraries.get(&effects.0) else {
+        requests.clear();
+        return;
+    };
+    let mut rng = rand::rng();
+
+    for request in requests.read() {
+        let Some(def) = library.effects.get(request.effect.key()) else {
+            continue;
+        };
+        let material = effect_materials.0.entry(request.effect).or_insert_with(|| {
+            let (r, g, b) = def.color;
+            materials.add(StandardMaterial {
+                base_color: LinearRgba::rgb(r, g, b).into(),
+                unlit: true,
+                alpha_mode: AlphaMode::Add,
+                ..default()
+            })
+        });
+        let axis = request.direction.unwrap_or(Vec3::Y);
+        for _ in 0..def.count {
+            let Some(entity) = pool.free.pop() else {
+                return;
+            };
+            let Ok((mut particle, mut transform, mut visibility, mut particle_material)) =
+                particles.get_mut(entity)
+            else {
+                continue;
+            };
+            *particle = Particle {
+                velocity: cone_direction(axis, def.spread, &mut rng) * range(&mut rng, def.speed),
+                age: 0.,
+                lifetime: range(&mut rng, def.lifetime).max(f32::EPSILON),
+                size: def.size,
+                gravity: def.gravity,
+            };
+            transform.translation = request.position;
+            transform.scale = Vec3::splat(def.size.0);
+            *visibility = Visibility::Visible;
+            if particle_material.0 != *material {
+                particle_material.0 = material.clone();
+            }
+        }
+    }
+}
+
+/// Moves live particles, shrinks them over their life and faces them to the camera.
+fn update_particles(
+    time: Res<Time>,
+    camera: Query<&GlobalTransform, With<GameCamera>>,
+    mut pool: ResMut<ParticlePool>,
+    mut particles: Query<(Entity, &mut Particle, &mut Transform, &mut Visibility)>,
+) {
+    let facing = camera
+        .single()
+        .map_or(Quat::IDENTITY, |camera| camera.rotation());
+    let delta = time.delta_secs();
+
+    for (entity, mut particle, mut transform, mut visibility) in &mut particles {
+        if *visibility == Visibility::Hidden {
+            continue;
+        }
+        particle.age += delta;
+        if particle.age >= particle.lifetime {
+            *visibility = Visibility::Hidden;
+            pool.free.push(entity);
+            continue;
+        }
+
+        let life = particle.age / particle.lifetime;
+        particle.velocity.y += particle.gravity * delta;
+        transform.translation += particle.velocity * delta;
+        transform.rotation = facing;
+        transform.scale = Vec3::splat(particle.size.0.lerp(particle.size.1, life));
+    }
+}
@@ -0,0 +1,254 @@
+use bevy::{prelude::*, window::WindowFocused};
+
+use crate::{
+    AppState,
+    plugins::{
+        locale::Localized,
+        menu::{MENU_ITEM_COLOR, MenuActivated, MenuItem},
+        net::{SimulationSystems, is_authority},
+        settings::{SETTING_FIELDS, spawn_setting_rows},
+        wave::GamePhase,
+    },
+};
+
+pub struct PausePlugin;
+
+/// Only a wave in progress can be paused, the shop already waits for the players.
+#[derive(SubStates, Default, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[source(GamePhase = GamePhase::Combat)]
+pub enum Pause {
+    #[default]
+    Running,
+    Paused,
+}
+
+#[derive(Component)]
+enum PauseAction {
+    Resume,
+    Settings,
+    Restart,
+    Quit,
+    Back,
+}
+
+/// Swapped between the buttons and the settings.
+#[derive(Component)]
+struct PauseContent;
+
+/// Sink that was playing when the game was paused, resumed with it.
+#[derive(Component)]
+struct PausedAudio;
+
+impl Plugin for PausePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_sub_state::<Pause>()
+            .configure_sets(
+                Update,
+                SimulationSystems.run_if(not(in_state(Pause::Paused))),
+            )
+            .add_systems(OnEnter(Pause::Paused), (freeze_game, spawn_pause_menu))
+            .add_systems(OnExit(Pause::Paused), resume_game)
+            .add_systems(OnExit(AppState::InGame), clear_run)
+            .add_systems(
+                Update,
+                // A client cannot stop the host's simulation, so only the authority pauses.
+                (toggle_pause, pause_on_focus_loss)
+                    .run_if(in_state(GamePhase::Combat))
+                    .run_if(is_authority),
+            )
+            .add_systems(Update, handle_pause_actions.run_if(in_state(Pause::Paused)));
+    }
+}
+
+fn toggle_pause(
+    keyboard: Res<ButtonInput<KeyCode>>,
+    gamepads: Query<&Gamepad>,
+    pause: Res<State<Pause>>,
+    mut next_pause: ResMut<NextState<Pause>>,
+) {
+    let pressed = keyboard.just_pressed(KeyCode::Escape)
+        || gamepads
+            .iter()
+            .any(|gamepad| gamepad.just_pressed(GamepadButton::Start));
+    if !pressed {
+        return;
+    }
+    next_pause.set(match pause.get() {
+        Pause::Running => Pause::Paused,
+        Pause::Paused => Pause::Running,
+    });
+}
+
+fn pause_on_focus_loss(
+    mut focus: MessageReader<WindowFocused>,
+    mut next_pause: ResMut<NextState<Pause>>,
+) {
+    if focus.read().any(|focus| !focus.focused) {
+        (*next_pause).set_if_neq(Pause::Paused);
+    }
+}
+
+fn freeze_game(
+    mut time: ResMut<Time<Virtual>>,
+    sinks: Query<(Entity, &AudioSink)>,
+    mut commands: Commands,
+) {
+    time.pause();
+    for (entity, sink) in &sinks {
+        if !sink.is_paused() {
+            sink.pause();
+            commands.entity(entity).insert(PausedAudio);
+        }
+    }
+}
+
+/// Also runs when the run ends while paused, so time never stays frozen.
+fn resume_game(
+    mut time: ResMut<Time<Virtual>>,
+    sinks: Query<(Entity, &AudioSink), With<PausedAudio>>,
+    mut commands: Commands,
+) {
+    time.unpause();
+    for (entity, sink) in &sinks {
+        sink.play();
+        commands.entity(entity).remove::<PausedAudio>();
+    }
+}
+
+fn pause_button(order: usize, action: PauseAction, key: &'static str) -> impl Bundle {
+    (
+        MenuItem(order),
+        action,
+        Node {
+            width: px(280),
+            padding: UiRect::axes(px(16), px(12)),
+            margin: UiRect::vertical(px(6)),
+            justify_content: JustifyContent::Center,
+            ..default()
+        },
+        BackgroundColor(MENU_ITEM_COLOR),
+        children![(
+            Localized::new(key),
+            TextFont {
+                font_size: 22.,
+                ..default()
+            },
+        )],
+    )
+}
+
+fn spawn_buttons(content: &mut ChildSpawnerCommands) {
+    content.spawn((
+        Localized::new("pause.title"),
+        TextFont {
+            font_size: 40.,
+            ..default()
+        },
+        Node {
+            margin: UiRect::bottom(px(12)),
+            ..default()
+        },
+    ));
+    content.spawn(pause_button(0, PauseAction::Resume, "pause.resume"));
+    content.spawn(pause_button(1, PauseAction::Settings, "menu.settings"));
+    content.spawn(pause_button(2, PauseAction::Restart, "pause.restart"));
+    content.spawn(pause_button(3, PauseAction::Quit, "pause.quit"));
+}
+
+fn spawn_settings(content: &mut ChildSpawnerCommands) {
+    content.spawn((
+        Localized::new("menu.settings"),
+        TextFont {
+            font_size: 28.,
+            ..default()
+        },
+        Node {
+            margin: UiRect::bottom(px(12)),
+            ..default()
+        },
+    ));
+    spawn_setting_rows(content, 0);
+    content.spawn(pause_button(
+        SETTING_FIELDS.len(),
+        PauseAction::Back,
+        "menu.back",
+    ));
+}
+
+fn spawn_pause_menu(mut commands: Commands) {
+    commands
+        .spawn((
+            DespawnOnExit(Pause::Paused),
+            Node {
+                position_type: PositionType::Absolute,
+                width: percent(100),
+                height: percent(100),
+                display: Display::Flex,
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                ..default()
+            },
+            BackgroundColor(Color::srgba_u8(0, 0, 0, 160)),
+            GlobalZIndex(1),
+        ))
+        .with_children(|root| {
+            root.spawn((
+                PauseContent,
+                Node {
+                    display: Display::Flex,
+                    flex_direction: FlexDirection::Column,
+                    align_items: AlignItems::Center,
+                    padding: UiRect::all(px(24)),
+                    ..default()
+                },
+            ))
+            .with_children(spawn_buttons);
+        });
+}
+
+fn handle_pause_actions(
+    mut activations: MessageReader<MenuActivated>,
+    actions: Query<&PauseAction>,
+    content: Single<Entity, With<PauseContent>>,
+    mut next_pause: ResMut<NextState<Pause>>,
+    mut next_phase: ResMut<NextState<GamePhase>>,
+    mut next_state: ResMut<NextState<AppState>>,
+    mut commands: Commands,
+) {
+    for activation in activations.read() {
+        match actions.get(activation.item) {
+            Ok(PauseAction::Resume) => next_pause.set(Pause::Running),
+            Ok(PauseAction::Settings) => {
+                commands
+                    .entity(*content)
+                    .despawn_related::<Children>()
+                    .with_children(spawn_settings);
+            }
+            Ok(PauseAction::Back) => {
+                commands
+                    .entity(*content)
+                    .despawn_related::<Children>()
+                    .with_children(spawn_buttons);
+            }
+            // Sub-states keep their value when their source is re-entered, so the wave is
+            // restarted and unpaused explicitly.
+            Ok(PauseAction::Restart) => {
+                next_state.set(AppState::InGame);
+                next_phase.set(GamePhase::Combat);
+                next_pause.set(Pause::Running);
+            }
+            Ok(PauseAction::Quit) => next_state.set(AppState::MainMenu),
+            Err(_) => {}
+        }
+    }
+}
+
+/// [`DespawnOnExit`] is skipped when a restart re-enters [`AppState::InGame`] from itself, so
+/// the previous run is cleared here before the new one is spawned.
+fn clear_run(scoped: Query<(Entity, &DespawnOnExit<AppState>)>, mut commands: Commands) {
+    for (entity, scope) in &scoped {
+        if scope.0 == AppState::InGame {
+            commands.entity(entity).try_despawn();
+        }
+    }
+}
@@ -0,0 +1,195 @@
+use bevy::prelude::*;
+
+use crate::{
+    AppState,
+    plugins::{
+        accessibility::Swatch,
+        enemy::EnemyKilled,
+        net::SimulationSystems,
+        player::Player,
+        powerup::{PowerUpCollected, PowerUpKind},
+        settings::Settings,
+        stats::RunStats,
+        upgrade::Stat,
+    },
+};
+
+pub struct PickupPlugin;
+
+/// Collectible dropped in the arena, despawned once `lifetime` runs out.
+#[derive(Component)]
+pub struct Pickup {
+    pub kind: PickupKind,
+    pub lifetime: Timer,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub enum PickupKind {
+    Coins(u32),
+    PowerUp(PowerUpKind),
+}
+
+#[derive(Resource)]
+pub struct PickupModels {
+    pub coin_mesh: Handle<Mesh>,
+    pub coin_material: Handle<StandardMaterial>,
+}
+
+const PICKUP_LIFETIME: f32 = 10.;
+/// Pickups start blinking when less than this many seconds are left.
+const PICKUP_BLINK_TIME: f32 = 3.;
+const PICKUP_COLLECT_RADIUS: f32 = 0.7;
+const PICKUP_MAGNET_SPEED: f32 = 6.;
+const PICKUP_HEIGHT: f32 = 0.3;
+
+impl Plugin for PickupPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Startup, insert_pickup_models)
+            .add_systems(
+                Update,
+                recolor_pickup_models.run_if(resource_changed::<Settings>),
+            )
+            .add_systems(
+                Update,
+                (drop_coins, attract_pickups, collect_pickups, expire_pickups)
+                    .chain()
+                    .in_set(SimulationSystems),
+            );
+    }
+}
+
+/// Coins glow in their own color so they stand out from the board.
+fn coin_material(color: Color) -> StandardMaterial {
+    StandardMaterial {
+        base_color: color,
+        emissive: LinearRgba::from(color) * 0.6,
+        ..default()
+    }
+}
+
+fn insert_pickup_models(
+    mut commands: Commands,
+    settings: Res<Settings>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    commands.insert_resource(PickupModels {
+        coin_mesh: meshes.add(Cylinder::new(0.25, 0.08)),
+        coin_material: materials.add(coin_material(settings.palette.color(Swatch::Coin))),
+    });
+}
+
+fn recolor_pickup_models(
+    settings: Res<Settings>,
+    models: Res<PickupModels>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    if let Some(material) = materials.get_mut(&models.coin_material) {
+        *material = coin_material(settings.palette.color(Swatch::Coin));
+    }
+}
+
+fn drop_coins(
+    mut kills: MessageReader<EnemyKilled>,
+    models: Res<PickupModels>,
+    mut commands: Commands,
+) {
+    for kill in kills.read().filter(|kill| kill.coins > 0) {
+        commands.spawn((
+            DespawnOnExit(AppState::InGame),
+            Pickup {
+                kind: PickupKind::Coins(kill.coins),
+                lifetime: Timer::from_seconds(PICKUP_LIFETIME, TimerMode::Once),
+            },
+            Mesh3d(models.coin_mesh.clone()),
+            MeshMaterial3d(models.coin_material.clone()),
+            Transform::from_xyz(kill.position.x, PICKUP_HEIGHT, kill.position.z),
+        ));
+    }
+}
+
+/// Pulls pickups toward the closest player whose magnet radius reaches them.
+fn attract_pickups(
+    mut pickups: Query<&mut Transform, (With<Pickup>, Without<Player>)>,
+    players: Query<(&Player, &Transform)>,
+    time: Res<Time>,
+) {
+    for mut pickup in &mut pickups {
+        let closest = players
+            .iter()
+            .filter(|(player, transform)| {
+                pickup.translation.distance(transform.translation)
+                    <= player.upgrades.stat(Stat::MagnetRadius)
+            })
+            .min_by(|(_, a), (_, b)| {
+                let a = pickup.translation.distance_squared(a.translation);
+                let b = pickup.translation.distance_squared(b.translation);
+                a.total_cmp(&b)
+            });
+
+        if let Some((_, target)) = closest {
+            let target = Vec3::new(target.translation.x, PICKUP_HEIGHT, target.translation.z);
+            let step = PICKUP_MAGNET_SPEED * time.delta_secs();
+            let offset = target - pickup.translation;
+            pickup.translation += offset.clamp_length_max(step);
+        }
+    }
+}
+
+fn collect_pickups(
+    pickups: Query<(Entity, &Pickup, &Transform), Without<Player>>,
+    mut players: Query<(Entity, &mut Player, &Transform)>,
+    mut power_ups: MessageWriter<PowerUpCollected>,
+    mut stats: ResMut<RunStats>,
+    mut commands: Commands,
+) {
+    for (entity, pickup, transform) in &pickups {
+        let collector = players.iter_mut().find(|(_, _, player_transform)| {
+            transform
+                .translation
+                .xz()
+                .distance(player_transform.translation.xz())
+                <= PICKUP_COLLECT_RADIUS
+        });
+
+        let Some((player_entity, mut player, _)) = collector else {
+            continue;
+        };
+
+        match pickup.kind {
+            PickupKind::Coins(amount) => {
+                player.coins += amount;
+                stats.coins_earned += amount;
+            }
+            PickupKind::PowerUp(kind) => {
+                power_ups.write(PowerUpCollected {
+                    player: player_entity,
+                    kind,
+                });
+            }
+        }
+        commands.entity(entity).despawn();
+    }
+}
+
+fn expire_pickups(
+    mut pickups: Query<(Entity, &mut Pickup, &mut Visibility)>,
+    mut commands: Commands,
+    time: Res<Time>,
+) {
+    for (entity, mut pickup, mut visibility) in &mut pickups {
+        pickup.lifetime.tick(time.delta());
+        if pickup.lifetime.is_finished() {
+            commands.entity(entity).despawn();
+            continue;
+        }
+
+        let remaining = pickup.lifetime.remaining_secs();
+        *visibility =
+            if remaining < PICKUP_BLINK_TIME && ((remaining * 8.) as u32).is_multiple_of(2) {
+                Visibility::Hidden
+            } else {
+                Visibility::Inherited
+            };
+    }
+}
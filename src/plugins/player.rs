@@ -1,79 +1,274 @@
-use bevy::prelude::*;
+use avian3d::prelude::{Collider, GravityScale, LinearVelocity, LockedAxes, RigidBody};
+use bevy::{
+    input::gamepad::{GamepadConnection, GamepadConnectionEvent},
+    prelude::*,
+};
 
 use crate::{
-    Cpu, DespawnOnFinish,
-    plugins::{gun::GunShootEvent, health::Health, ui::CpuHealthBar},
+    AppState,
+    plugins::{
+        accessibility::Swatch,
+        glyphs::{GlyphSet, LastDevice, keyboard_key},
+        gun::GunShootEvent,
+        health::Health,
+        model::{Model, ModelKind, Tint},
+        net::{SimulationSystems, is_authority},
+        powerup::{ActiveBuffs, PowerUpKind, RAPID_FIRE_COOLDOWN},
+        settings::Settings,
+        upgrade::{Stat, UPGRADES, UpgradeId, UpgradeLevels, shoot_cooldown_timer},
+        wave::GamePhase,
+    },
 };
 
 pub struct PlayerPlugin;
 
-#[derive(Resource)]
-pub struct PlayerLevelUpAudio(pub Handle<AudioSource>);
-
 #[derive(Component)]
 pub struct Player {
+    /// 1-based seat number, reused once its player leaves.
+    pub number: usize,
     pub coins: u32,
-    pub fire_rate_level: u32,
-    pub damage_level: u32,
-    pub speed_level: u32,
+    pub kills: u32,
+    pub upgrades: UpgradeLevels,
 }
 
-pub const UPGRADE_COST: u32 = 10;
+/// Gamepad driving a local player.
+#[derive(Component)]
+pub struct PlayerGamepad(pub Entity);
+
+/// Intent of a player for the current frame, whatever device it comes from.
+#[derive(Component, Default)]
+pub struct PlayerInput {
+    pub movement: Vec2,
+    pub aim: Vec2,
+    pub shoot: bool,
+    pub purchase: Option<UpgradeId>,
+}
+
+impl PlayerInput {
+    pub fn from_gamepad(gamepad: &Gamepad) -> Self {
+        let stick = gamepad.left_stick();
+        Self {
+            movement: Vec2::new(
+                if stick.x.abs() > 0.01 { stick.x } else { 0. },
+                if stick.y.abs() > 0.01 { stick.y } else { 0. },
+            ),
+            aim: gamepad.right_stick(),
+            shoot: gamepad.just_pressed(GamepadButton::RightTrigger2),
+            purchase: UPGRADES
+                .iter()
+                .find(|def| {
+                    def.button
+                        .is_some_and(|button| gamepad.just_pressed(button))
+                })
+                .map(|def| def.id),
+        }
+    }
+
+    /// WASD to move, arrows to aim, and the keys of [`keyboard_key`] for buttons.
+    pub fn from_keyboard(keyboard: &ButtonInput<KeyCode>) -> Self {
+        let axis = |negative, positive| {
+            keyboard.pressed(positive) as i8 as f32 - keyboard.pressed(negative) as i8 as f32
+        };
+        let just_pressed =
+            |button| keyboard_key(button).is_some_and(|key| keyboard.just_pressed(key));
+        Self {
+            movement: Vec2::new(
+                axis(KeyCode::KeyA, KeyCode::KeyD),
+                axis(KeyCode::KeyS, KeyCode::KeyW),
+            )
+            .normalize_or_zero(),
+            aim: Vec2::new(
+                axis(KeyCode::ArrowLeft, KeyCode::ArrowRight),
+                axis(KeyCode::ArrowDown, KeyCode::ArrowUp),
+            )
+            .normalize_or_zero(),
+            shoot: just_pressed(GamepadButton::RightTrigger2),
+            purchase: UPGRADES
+                .iter()
+                .find(|def| def.button.is_some_and(just_pressed))
+                .map(|def| def.id),
+        }
+    }
+}
 
 #[derive(Component)]
 pub struct ShootCooldown(pub Timer);
 
-const BASE_SHOOT_COOLDOWN: f32 = 0.5;
-const COOLDOWN_REDUCTION_PER_LEVEL: f32 = 0.08;
+/// Systems filling [`PlayerInput`] before gameplay systems consume it.
+#[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ReadPlayerInput;
+
+/// Lowest seat number not in `taken`.
+pub fn free_player_number(taken: &[usize]) -> usize {
+    (1..).find(|n| !taken.contains(n)).unwrap()
+}
 
 impl Plugin for PlayerPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(Startup, (spawn_player, spawn_player_audio))
-            .add_systems(Update, (handle_player_move, handle_upgrades));
+        app.add_systems(
+            OnEnter(AppState::InGame),
+            spawn_connected_players.run_if(is_authority),
+        )
+        .add_systems(
+            Update,
+            handle_gamepad_connections
+                .in_set(SimulationSystems)
+                .run_if(in_state(AppState::InGame)),
+        )
+        .add_systems(
+            Update,
+            read_local_input
+                .in_set(ReadPlayerInput)
+                .in_set(SimulationSystems)
+                .run_if(in_state(GamePhase::Combat)),
+        )
+        .add_systems(OnExit(GamePhase::Combat), stop_players)
+        .add_systems(PostUpdate, paint_players)
+        .add_systems(
+            Update,
+            handle_player_move
+                .after(ReadPlayerInput)
+                .in_set(SimulationSystems)
+                .run_if(in_state(GamePhase::Combat)),
+        );
     }
 }
 
-fn spawn_player_audio(asset_server: Res<AssetServer>, mut commands: Commands) {
-    commands.insert_resource(PlayerLevelUpAudio(asset_server.load("level.ogg")));
+/// Seats every gamepad already connected when the run starts.
+///
+/// Player 1 is seated even without one, on the keyboard until a gamepad connects.
+fn spawn_connected_players(
+    gamepads: Query<Entity, With<Gamepad>>,
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    let mut seats: Vec<Option<Entity>> = gamepads.iter().map(Some).collect();
+    if seats.is_empty() {
+        seats.push(None);
+    }
+    for (index, gamepad) in seats.into_iter().enumerate() {
+        let player = spawn_player(index + 1, &mut commands, &mut meshes, &mut materials);
+        if let Some(gamepad) = gamepad {
+            commands.entity(player).insert(PlayerGamepad(gamepad));
+        }
+    }
 }
 
-const PLAYER_BASE_SPEED: f32 = 2.;
-const PLAYER_SPEED_PER_LEVEL: f32 = 0.5;
+/// Seats newly connected gamepads, player 1's keyboard seat first, and frees the seats of
+/// disconnected ones. Player 1 falls back to the keyboard instead of leaving.
+fn handle_gamepad_connections(
+    mut connections: MessageReader<GamepadConnectionEvent>,
+    players: Query<(Entity, &Player, Option<&PlayerGamepad>)>,
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    let mut taken: Vec<usize> = players.iter().map(|(_, player, _)| player.number).collect();
+    // Whether player 1 is on the keyboard, updated as connections are handled this frame.
+    let mut keyboard_seat = players
+        .iter()
+        .find(|(_, player, gamepad)| player.number == 1 && gamepad.is_none())
+        .map(|(entity, ..)| entity);
+
+    for connection in connections.read() {
+        let bound = players
+            .iter()
+            .find(|(_, _, gamepad)| gamepad.is_some_and(|gamepad| gamepad.0 == connection.gamepad));
+
+        match (&connection.connection, bound) {
+            (GamepadConnection::Connected { .. }, None) => {
+                let player = match keyboard_seat.take() {
+                    Some(player) => player,
+                    None => {
+                        let number = free_player_number(&taken);
+                        taken.push(number);
+                        spawn_player(number, &mut commands, &mut meshes, &mut materials)
+                    }
+                };
+                commands
+                    .entity(player)
+                    .insert(PlayerGamepad(connection.gamepad));
+            }
+            (GamepadConnection::Disconnected, Some((entity, player, _))) if player.number == 1 => {
+                commands.entity(entity).remove::<PlayerGamepad>();
+                keyboard_seat = Some(entity);
+            }
+            (GamepadConnection::Disconnected, Some((entity, player, _))) => {
+                taken.retain(|n| *n != player.number);
+                commands.entity(entity).despawn();
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Player 1 takes the keyboard while it is the last device used or it has no gamepad, the others
+/// their gamepad.
+fn read_local_input(
+    gamepads: Query<&Gamepad>,
+    keyboard: Res<ButtonInput<KeyCode>>,
+    last_device: Res<LastDevice>,
+    mut players: Query<(&Player, Option<&PlayerGamepad>, &mut PlayerInput)>,
+) {
+    for (player, gamepad, mut input) in &mut players {
+        let Some(PlayerGamepad(gamepad)) = gamepad else {
+            if player.number == 1 {
+                *input = PlayerInput::from_keyboard(&keyboard);
+            }
+            continue;
+        };
+        *input = if player.number == 1 && last_device.0 == GlyphSet::Keyboard {
+            PlayerInput::from_keyboard(&keyboard)
+        } else {
+            gamepads
+                .get(*gamepad)
+                .map(PlayerInput::from_gamepad)
+                .unwrap_or_default()
+        };
+    }
+}
 
 fn handle_player_move(
     time: Res<Time>,
-    gamepads: Query<&Gamepad, Without<Player>>,
-    mut player_query: Query<(&Player, &mut Transform, &mut ShootCooldown)>,
+    mut player_query: Query<(
+        Entity,
+        &Player,
+        &PlayerInput,
+        &ActiveBuffs,
+        &mut Transform,
+        &mut LinearVelocity,
+        &mut ShootCooldown,
+    )>,
     mut shoots: MessageWriter<GunShootEvent>,
+    settings: Res<Settings>,
 ) {
-    let Ok((player_data, mut player, mut cooldown)) = player_query.single_mut() else {
-        return;
-    };
-    let speed = PLAYER_BASE_SPEED + player_data.speed_level as f32 * PLAYER_SPEED_PER_LEVEL;
+    for (entity, player_data, input, buffs, mut player, mut velocity, mut cooldown) in
+        &mut player_query
+    {
+        let speed =
+            player_data.upgrades.stat(Stat::MoveSpeed) * buffs.stat_multiplier(Stat::MoveSpeed);
 
-    cooldown.0.tick(time.delta());
+        cooldown.0.tick(time.delta());
+        let can_shoot = if buffs.has(PowerUpKind::RapidFire) {
+            cooldown.0.elapsed_secs() >= RAPID_FIRE_COOLDOWN
+        } else {
+            cooldown.0.is_finished()
+        };
 
-    for gamepad in &gamepads {
-        let left_stick_x = gamepad.get(GamepadAxis::LeftStickX).unwrap();
-        if left_stick_x.abs() > 0.01 {
-            player.translation.z -= left_stick_x * speed * time.delta_secs();
-        }
-        let left_stick_y = gamepad.get(GamepadAxis::LeftStickY).unwrap();
-        if left_stick_y.abs() > 0.01 {
-            player.translation.x -= left_stick_y * speed * time.delta_secs();
-        }
+        // Moved by the physics step, so the board's components block the way.
+        velocity.0 = Vec3::new(-input.movement.y, 0., -input.movement.x) * speed;
 
-        let x = gamepad.get(GamepadAxis::RightStickX).unwrap();
-        let y = gamepad.get(GamepadAxis::RightStickY).unwrap();
+        let Vec2 { x, y } = input.aim;
 
-        let dead_zone = 0.1;
+        let dead_zone = settings.aim_dead_zone;
 
         if x.abs() > dead_zone || y.abs() > dead_zone {
             let angle = (-x).atan2(y);
             player.rotation = Quat::from_rotation_y(angle);
         }
 
-        if gamepad.just_pressed(GamepadButton::RightTrigger2) && cooldown.0.is_finished() {
+        if input.shoot && can_shoot {
             cooldown.0.reset();
 
             let distance = 20.0;
@@ -92,73 +287,83 @@ fn handle_player_move(
             shoots.write(GunShootEvent {
                 source: origin,
                 target,
+                shooter: entity,
             });
         }
     }
 }
 
-fn handle_upgrades(
-    gamepads: Query<&Gamepad, Without<Player>>,
-    mut player_query: Query<(&mut Player, &mut ShootCooldown)>,
-    mut cpu_health: Single<&mut Health, With<Cpu>>,
-    mut health_bar: Single<&mut Node, With<CpuHealthBar>>,
-    leve_audio: Res<PlayerLevelUpAudio>,
-    mut commands: Commands,
-) {
-    let Ok((mut player, mut cooldown)) = player_query.single_mut() else {
-        return;
-    };
-
-    for gamepad in &gamepads {
-        if gamepad.just_pressed(GamepadButton::West) && player.coins >= UPGRADE_COST {
-            player.coins -= UPGRADE_COST;
-            player.fire_rate_level += 1;
-            let new_cooldown = (BASE_SHOOT_COOLDOWN
-                - player.fire_rate_level as f32 * COOLDOWN_REDUCTION_PER_LEVEL)
-                .max(0.05);
-            cooldown.0 = Timer::from_seconds(new_cooldown, TimerMode::Once);
-            commands.spawn((AudioPlayer::new(leve_audio.0.clone()), DespawnOnFinish));
-        }
-        if gamepad.just_pressed(GamepadButton::North) && player.coins >= UPGRADE_COST {
-            player.coins -= UPGRADE_COST;
-            player.damage_level += 1;
-            commands.spawn((AudioPlayer::new(leve_audio.0.clone()), DespawnOnFinish));
-        }
-        if gamepad.just_pressed(GamepadButton::East) && player.coins >= UPGRADE_COST {
-            player.coins -= UPGRADE_COST;
-            cpu_health.0 = (cpu_health.0 + 10.).min(100.);
-            health_bar.width = percent(cpu_health.0);
-            commands.spawn((AudioPlayer::new(leve_audio.0.clone()), DespawnOnFinish));
-        }
-        if gamepad.just_pressed(GamepadButton::South) && player.coins >= UPGRADE_COST {
-            player.coins -= UPGRADE_COST;
-            player.speed_level += 1;
-            commands.spawn((AudioPlayer::new(leve_audio.0.clone()), DespawnOnFinish));
-        }
+fn stop_players(mut velocities: Query<&mut LinearVelocity, With<Player>>) {
+    for mut velocity in &mut velocities {
+        velocity.0 = Vec3::ZERO;
     }
 }
 
-fn spawn_player(
-    mut commands: Commands,
-    mut meshes: ResMut<Assets<Mesh>>,
+/// Spawns the avatar of player `number`, offset so that seats do not overlap.
+pub fn spawn_player(
+    number: usize,
+    commands: &mut Commands,
+    meshes: &mut Assets<Mesh>,
+    materials: &mut Assets<StandardMaterial>,
+) -> Entity {
+    let seat_offset = (number as f32 - 1.) * 1.5;
+
+    commands
+        .spawn((
+            DespawnOnExit(AppState::InGame),
+            Player {
+                number,
+                coins: 0,
+                kills: 0,
+                upgrades: default(),
+            },
+            PlayerInput::default(),
+            ShootCooldown(shoot_cooldown_timer(&UpgradeLevels::default())),
+            ActiveBuffs::default(),
+            Mesh3d(meshes.add(Cuboid::new(1.0, 1.0, 1.0))),
+            // Colored by `paint_players`, from the palette in the settings.
+            MeshMaterial3d(materials.add(Color::WHITE)),
+            Model::new(ModelKind::Chara),
+            Transform::from_xyz(seat_offset, 0.5, 0.0),
+            RigidBody::Dynamic,
+            // Slightly smaller than the cuboid so it never scrapes the board's surface.
+            Collider::cuboid(0.8, 0.8, 0.8),
+            LockedAxes::ROTATION_LOCKED.lock_translation_y(),
+            GravityScale(0.),
+            Health(100.),
+            children![(
+                Mesh3d(meshes.add(Cuboid::new(1.0, 0.5, 0.5))),
+                MeshMaterial3d(materials.add(Color::srgb_u8(50, 255, 255))),
+                Transform::from_xyz(-0.5, 0.0, 0.0),
+            )],
+        ))
+        .id()
+}
+
+/// The placeholder material is gone once the model is ready, the tint stays.
+type PaintedPlayers<'w, 's> = Query<
+    'w,
+    's,
+    (
+        Ref<'static, Player>,
+        Option<&'static MeshMaterial3d<StandardMaterial>>,
+        &'static mut Tint,
+    ),
+>;
+
+fn paint_players(
+    settings: Res<Settings>,
+    mut players: PaintedPlayers,
     mut materials: ResMut<Assets<StandardMaterial>>,
 ) {
-    commands.spawn((
-        Player {
-            coins: 0,
-            fire_rate_level: 0,
-            damage_level: 0,
-            speed_level: 0,
-        },
-        ShootCooldown(Timer::from_seconds(BASE_SHOOT_COOLDOWN, TimerMode::Once)),
-        Mesh3d(meshes.add(Cuboid::new(1.0, 1.0, 1.0))),
-        MeshMaterial3d(materials.add(Color::srgb_u8(124, 144, 255))),
-        Transform::from_xyz(0.0, 0.5, 0.0),
-        Health(100.),
-        children![(
-            Mesh3d(meshes.add(Cuboid::new(1.0, 0.5, 0.5))),
-            MeshMaterial3d(materials.add(Color::srgb_u8(50, 255, 255))),
-            Transform::from_xyz(-0.5, 0.0, 0.0),
-        )],
-    ));
+    for (player, material, mut tint) in &mut players {
+        if !settings.is_changed() && !player.is_added() {
+            continue;
+        }
+        let color = settings.palette.color(Swatch::Player(player.number));
+        if let Some(material) = material.and_then(|material| materials.get_mut(material)) {
+            material.base_color = color;
+        }
+        tint.0 = color;
+    }
 }
@@ -0,0 +1,260 @@
+use std::collections::HashMap;
+
+use bevy::prelude::*;
+use rand::{Rng, seq::IndexedRandom};
+
+use crate::{
+    AppState,
+    plugins::{
+        accessibility::Swatch,
+        enemy::{Enemy, EnemyKilled, EnemyKind, KillCause},
+        health::Health,
+        net::SimulationSystems,
+        pickup::{Pickup, PickupKind},
+        player::Player,
+        settings::Settings,
+        upgrade::Stat,
+    },
+};
+
+pub struct PowerUpPlugin;
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum PowerUpKind {
+    DoubleDamage,
+    RapidFire,
+    SpeedBoost,
+    /// Enemies reaching the CPU deal no damage.
+    Shield,
+    /// Kills every enemy on the field instantly.
+    Bomb,
+}
+
+pub const POWER_UPS: &[PowerUpKind] = &[
+    PowerUpKind::DoubleDamage,
+    PowerUpKind::RapidFire,
+    PowerUpKind::SpeedBoost,
+    PowerUpKind::Shield,
+    PowerUpKind::Bomb,
+];
+
+impl PowerUpKind {
+    /// Locale key of the power-up's name.
+    pub fn key(self) -> &'static str {
+        match self {
+            PowerUpKind::DoubleDamage => "powerup.double_damage",
+            PowerUpKind::RapidFire => "powerup.rapid_fire",
+            PowerUpKind::SpeedBoost => "powerup.speed_boost",
+            PowerUpKind::Shield => "powerup.shield",
+            PowerUpKind::Bomb => "powerup.bomb",
+        }
+    }
+
+    /// How long the buff lasts, `None` for instant effects.
+    pub fn duration(self) -> Option<f32> {
+        match self {
+            PowerUpKind::DoubleDamage => Some(8.),
+            PowerUpKind::RapidFire => Some(6.),
+            PowerUpKind::SpeedBoost => Some(8.),
+            PowerUpKind::Shield => Some(10.),
+            PowerUpKind::Bomb => None,
+        }
+    }
+}
+
+pub struct Buff {
+    pub kind: PowerUpKind,
+    pub remaining: Timer,
+}
+
+/// Timed power-ups currently affecting a player.
+#[derive(Component, Default)]
+pub struct ActiveBuffs(pub Vec<Buff>);
+
+impl ActiveBuffs {
+    pub fn has(&self, kind: PowerUpKind) -> bool {
+        self.0.iter().any(|buff| buff.kind == kind)
+    }
+
+    pub fn remaining(&self, kind: PowerUpKind) -> Option<&Timer> {
+        self.0
+            .iter()
+            .find(|buff| buff.kind == kind)
+            .map(|buff| &buff.remaining)
+    }
+
+    pub fn stat_multiplier(&self, stat: Stat) -> f32 {
+        match stat {
+            Stat::Damage if self.has(PowerUpKind::DoubleDamage) => 2.,
+            Stat::MoveSpeed if self.has(PowerUpKind::SpeedBoost) => 1.5,
+            _ => 1.,
+        }
+    }
+}
+
+/// Sent when a player walks over a power-up pickup.
+#[derive(Message)]
+pub struct PowerUpCollected {
+    pub player: Entity,
+    pub kind: PowerUpKind,
+}
+
+#[derive(Resource)]
+pub struct PowerUpModels {
+    pub mesh: Handle<Mesh>,
+    pub materials: HashMap<PowerUpKind, Handle<StandardMaterial>>,
+}
+
+/// Cooldown used instead of the upgraded one while rapid fire is active.
+pub const RAPID_FIRE_COOLDOWN: f32 = 0.08;
+
+const POWER_UP_DROP_CHANCE: f64 = 0.08;
+const POWER_UP_LIFETIME: f32 = 12.;
+const POWER_UP_SPIN_SPEED: f32 = 2.;
+
+impl Plugin for PowerUpPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_message::<PowerUpCollected>()
+            .add_systems(Startup, insert_power_up_models)
+            .add_systems(
+                Update,
+                recolor_power_up_models.run_if(resource_changed::<Settings>),
+            )
+            .add_systems(
+                Update,
+                (drop_power_ups, spin_power_ups, apply_power_ups, tick_buffs)
+                    .in_set(SimulationSystems),
+            );
+    }
+}
+
+fn power_up_material(color: Color) -> StandardMaterial {
+    StandardMaterial {
+        base_color: color,
+        emissive: LinearRgba::from(color) * 0.6,
+        ..default()
+    }
+}
+
+fn insert_power_up_models(
+    mut commands: Commands,
+    settings: Res<Settings>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    let materials = POWER_UPS
+        .iter()
+        .map(|kind| {
+            let color = settings.palette.color(Swatch::PowerUp(*kind));
+            (*kind, materials.add(power_up_material(color)))
+        })
+        .collect();
+
+    commands.insert_resource(PowerUpModels {
+        mesh: meshes.add(Cuboid::new(0.45, 0.45, 0.45)),
+        materials,
+    });
+}
+
+fn recolor_power_up_models(
+    settings: Res<Settings>,
+    models: Res<PowerUpModels>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    for (kind, handle) in &models.materials {
+        if let Some(material) = materials.get_mut(handle) {
+            *material = power_up_material(settings.palette.color(Swatch::PowerUp(*kind)));
+        }
+    }
+}
+
+fn drop_power_ups(
+    mut kills: MessageReader<EnemyKilled>,
+    models: Res<PowerUpModels>,
+    mut commands: Commands,
+) {
+    let mut rng = rand::rng();
+    // Bomb victims must not roll another drop.
+    for kill in kills
+        .read()
+        .filter(|kill| matches!(kill.cause, KillCause::Bullet { .. }))
+    {
+        if !rng.random_bool(POWER_UP_DROP_CHANCE) {
+            continue;
+        }
+        let Some(kind) = POWER_UPS.choose(&mut rng).copied() else {
+            continue;
+        };
+
+        commands.spawn((
+            DespawnOnExit(AppState::InGame),
+            Pickup {
+                kind: PickupKind::PowerUp(kind),
+                lifetime: Timer::from_seconds(POWER_UP_LIFETIME, TimerMode::Once),
+            },
+            Mesh3d(models.mesh.clone()),
+            MeshMaterial3d(models.materials[&kind].clone()),
+            Transform::from_xyz(kill.position.x + 0.4, 0.4, kill.position.z),
+        ));
+    }
+}
+
+fn spin_power_ups(mut pickups: Query<(&mut Transform, &Pickup)>, time: Res<Time>) {
+    for (mut transform, pickup) in &mut pickups {
+        if let PickupKind::PowerUp(_) = pickup.kind {
+            transform.rotate_y(POWER_UP_SPIN_SPEED * time.delta_secs());
+        }
+    }
+}
+
+fn apply_power_ups(
+    mut collected: MessageReader<PowerUpCollected>,
+    mut buffs: Query<&mut ActiveBuffs>,
+    mut players: Query<&mut Player>,
+    mut enemies: Query<(&mut Health, &Transform, &EnemyKind), With<Enemy>>,
+    mut kills: MessageWriter<EnemyKilled>,
+) {
+    for PowerUpCollected { player, kind } in collected.read() {
+        let Some(duration) = kind.duration() else {
+            for (mut health, transform, kind) in &mut enemies {
+                if health.0 > 0. {
+                    health.0 = 0.;
+                    if let Ok(mut collector) = players.get_mut(*player) {
+                        collector.kills += 1;
+                    }
+                    kills.write(EnemyKilled {
+                        kind: *kind,
+                        position: transform.translation,
+                        coins: 0,
+                        cause: KillCause::Bomb { player: *player },
+                    });
+                }
+            }
+            continue;
+        };
+
+        let Ok(mut buffs) = buffs.get_mut(*player) else {
+            continue;
+        };
+        let timer = Timer::from_seconds(duration, TimerMode::Once);
+        match buffs.0.iter_mut().find(|buff| buff.kind == *kind) {
+            Some(buff) => buff.remaining = timer,
+            None => buffs.0.push(Buff {
+                kind: *kind,
+                remaining: timer,
+            }),
+        }
+    }
+}
+
+fn tick_buffs(mut buffs: Query<&mut ActiveBuffs>, time: Res<Time>) {
+    for mut buffs in &mut buffs {
+        if buffs.0.is_empty() {
+            continue;
+        }
+        for buff in &mut buffs.0 {
+            buff.remaining.tick(time.delta());
+        }
+        buffs.0.retain(|buff| !buff.remaining.is_finished());
+    }
+}
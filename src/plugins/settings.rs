@@ -0,0 +1,401 @@
+use std::{fs, path::PathBuf};
+
+use bevy::{
+    audio::Volume,
+    prelude::*,
+    window::{PresentMode, PrimaryWindow, WindowMode},
+};
+use serde::{Deserialize, Serialize};
+
+use crate::plugins::{
+    accessibility::{PALETTES, Palette},
+    locale::{LANGUAGES, Language, Locale},
+    menu::{MENU_ITEM_COLOR, MenuActivated, MenuAdjusted, MenuItem},
+    sound::AudioBus,
+};
+
+pub struct SettingsPlugin;
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum WindowSetting {
+    Windowed,
+    Borderless,
+    Fullscreen,
+}
+
+pub const WINDOW_SETTINGS: &[WindowSetting] = &[
+    WindowSetting::Windowed,
+    WindowSetting::Borderless,
+    WindowSetting::Fullscreen,
+];
+
+impl WindowSetting {
+    pub fn key(self) -> &'static str {
+        match self {
+            WindowSetting::Windowed => "settings.windowed",
+            WindowSetting::Borderless => "settings.borderless",
+            WindowSetting::Fullscreen => "settings.fullscreen",
+        }
+    }
+}
+
+const RESOLUTIONS: &[(u32, u32)] = &[(1280, 720), (1600, 900), (1920, 1080), (2560, 1440)];
+
+/// Player options, saved to the config directory and applied as soon as they change.
+#[derive(Resource, Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct Settings {
+    pub master_volume: f32,
+    pub sfx_volume: f32,
+    pub music_volume: f32,
+    pub window_mode: WindowSetting,
+    /// Window size when [`WindowSetting::Windowed`], ignored otherwise.
+    pub resolution: (u32, u32),
+    pub vsync: bool,
+    pub ui_scale: f32,
+    /// Aim stick deflection below which the player keeps facing the same way.
+    pub aim_dead_zone: f32,
+    /// Whether the camera shakes when the CPU takes damage.
+    pub screen_shake: bool,
+    pub language: Language,
+    /// Colors of gameplay materials and the HUD.
+    pub palette: Palette,
+    /// Outlines HUD panels and menu items.
+    pub high_contrast: bool,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            master_volume: 1.,
+            sfx_volume: 1.,
+            music_volume: 0.7,
+            window_mode: WindowSetting::Windowed,
+            resolution: RESOLUTIONS[0],
+            vsync: true,
+            ui_scale: 1.,
+            aim_dead_zone: 0.1,
+            screen_shake: true,
+            language: Language::default(),
+            palette: Palette::default(),
+            high_contrast: false,
+        }
+    }
+}
+
+impl Settings {
+    /// Volume of `bus`, on top of the global master volume.
+    pub fn bus_volume(&self, bus: AudioBus) -> Volume {
+        Volume::Linear(match bus {
+            AudioBus::Sfx => self.sfx_volume,
+            AudioBus::Music => self.music_volume,
+        })
+    }
+
+    fn path() -> Option<PathBuf> {
+        dirs::config_dir().map(|dir| dir.join("imissmyram").join("settings.ron"))
+    }
+
+    /// Missing fields take their default, so older files keep working.
+    fn load() -> Self {
+        let Some(path) = Self::path() else {
+            return Self::default();
+        };
+        let Ok(contents) = fs::read_to_string(&path) else {
+            return Self::default();
+        };
+        ron::from_str(&contents).unwrap_or_else(|error| {
+            warn!("Settings at {} are invalid: {error}", path.display());
+            Self::default()
+        })
+    }
+
+    fn save(&self) {
+        let Some(path) = Self::path() else {
+            warn!("No config directory, settings will not be saved");
+            return;
+        };
+        let result = ron::ser::to_string_pretty(self, default())
+            .map_err(|error| error.to_string())
+            .and_then(|contents| {
+                if let Some(dir) = path.parent() {
+                    fs::create_dir_all(dir).map_err(|error| error.to_string())?;
+                }
+                let temporary = path.with_extension("ron.tmp");
+                fs::write(&temporary, contents).map_err(|error| error.to_string())?;
+                fs::rename(&temporary, &path).map_err(|error| error.to_string())
+            });
+        if let Err(error) = result {
+            warn!("Could not save settings to {}: {error}", path.display());
+        }
+    }
+}
+
+/// One line of the settings screen.
+#[derive(Component, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum SettingField {
+    MasterVolume,
+    SfxVolume,
+    MusicVolume,
+    WindowMode,
+    Resolution,
+    Vsync,
+    UiScale,
+    AimDeadZone,
+    ScreenShake,
+    Palette,
+    HighContrast,
+    Language,
+}
+
+pub const SETTING_FIELDS: &[SettingField] = &[
+    SettingField::MasterVolume,
+    SettingField::SfxVolume,
+    SettingField::MusicVolume,
+    SettingField::WindowMode,
+    SettingField::Resolution,
+    SettingField::Vsync,
+    SettingField::UiScale,
+    SettingField::AimDeadZone,
+    SettingField::ScreenShake,
+    SettingField::Palette,
+    SettingField::HighContrast,
+    SettingField::Language,
+];
+
+#[derive(Component)]
+struct SettingText(SettingField);
+
+/// Moves `value` by `step` increments of `increment`, clamped to `min..=max`.
+///
+/// With `wrap`, stepping past `max` goes back to `min`, so a mouse click can reach every value.
+fn step_value(value: f32, step: i32, increment: f32, min: f32, max: f32, wrap: bool) -> f32 {
+    let next = ((value / increment).round() + step as f32) * increment;
+    if wrap && next > max + increment / 2. {
+        min
+    } else {
+        next.clamp(min, max)
+    }
+}
+
+pub fn on_off(value: bool) -> &'static str {
+    if value { "settings.on" } else { "settings.off" }
+}
+
+fn step_index(index: usize, step: i32, len: usize) -> usize {
+    (index as i32 + step).rem_euclid(len as i32) as usize
+}
+
+impl SettingField {
+    /// Locale key of the line, given the current value as `value`.
+    pub fn key(self) -> &'static str {
+        match self {
+            SettingField::MasterVolume => "settings.master_volume",
+            SettingField::SfxVolume => "settings.sfx_volume",
+            SettingField::MusicVolume => "settings.music_volume",
+            SettingField::WindowMode => "settings.window",
+            SettingField::Resolution => "settings.resolution",
+            SettingField::Vsync => "settings.vsync",
+            SettingField::UiScale => "settings.ui_scale",
+            SettingField::AimDeadZone => "settings.aim_dead_zone",
+            SettingField::ScreenShake => "settings.screen_shake",
+            SettingField::Palette => "settings.palette",
+            SettingField::HighContrast => "settings.high_contrast",
+            SettingField::Language => "settings.language",
+        }
+    }
+
+    fn describe(self, settings: &Settings, locale: &Locale) -> String {
+        let percent = |value: f32| format!("{}%", (value * 100.).round());
+        let value = match self {
+            SettingField::MasterVolume => percent(settings.master_volume),
+            SettingField::SfxVolume => percent(settings.sfx_volume),
+            SettingField::MusicVolume => percent(settings.music_volume),
+            SettingField::WindowMode => locale.get(settings.window_mode.key()),
+            SettingField::Resolution => {
+                let (width, height) = settings.resolution;
+                return locale.format(self.key(), &[("width", &width), ("height", &height)]);
+            }
+            SettingField::Vsync => locale.get(on_off(settings.vsync)),
+            SettingField::UiScale => percent(settings.ui_scale),
+            SettingField::AimDeadZone => percent(settings.aim_dead_zone),
+            SettingField::ScreenShake => locale.get(on_off(settings.screen_shake)),
+            SettingField::Palette => locale.get(settings.palette.key()),
+            SettingField::HighContrast => locale.get(on_off(settings.high_contrast)),
+            // Always in its own language, so it can be found from any other.
+            SettingField::Language => settings.language.native_name().to_string(),
+        };
+        locale.format(self.key(), &[("value", &value)])
+    }
+
+    fn adjust(self, settings: &mut Settings, step: i32, wrap: bool) {
+        match self {
+            SettingField::MasterVolume => {
+                settings.master_volume = step_value(settings.master_volume, step, 0.1, 0., 1., wrap)
+            }
+            SettingField::SfxVolume => {
+                settings.sfx_volume = step_value(settings.sfx_volume, step, 0.1, 0., 1., wrap)
+            }
+            SettingField::MusicVolume => {
+                settings.music_volume = step_value(settings.music_volume, step, 0.1, 0., 1., wrap)
+            }
+            SettingField::WindowMode => {
+                let index = WINDOW_SETTINGS
+                    .iter()
+                    .position(|mode| *mode == settings.window_mode)
+                    .unwrap_or(0);
+                settings.window_mode =
+                    WINDOW_SETTINGS[step_index(index, step, WINDOW_SETTINGS.len())];
+            }
+            SettingField::Resolution => {
+                let index = RESOLUTIONS
+                    .iter()
+                    .position(|resolution| *resolution == settings.resolution)
+                    .unwrap_or(0);
+                settings.resolution = RESOLUTIONS[step_index(index, step, RESOLUTIONS.len())];
+            }
+            SettingField::Vsync => settings.vsync = !settings.vsync,
+            SettingField::ScreenShake => settings.screen_shake = !settings.screen_shake,
+            SettingField::HighContrast => settings.high_contrast = !settings.high_contrast,
+            SettingField::Palette => {
+                let index = PALETTES
+                    .iter()
+                    .position(|palette| *palette == settings.palette)
+                    .unwrap_or(0);
+                settings.palette = PALETTES[step_index(index, step, PALETTES.len())];
+            }
+            SettingField::UiScale => {
+                settings.ui_scale = step_value(settings.ui_scale, step, 0.25, 0.5, 2., wrap)
+            }
+            SettingField::AimDeadZone => {
+                settings.aim_dead_zone =
+                    step_value(settings.aim_dead_zone, step, 0.05, 0., 0.5, wrap)
+            }
+            SettingField::Language => {
+                let index = LANGUAGES
+                    .iter()
+                    .position(|language| *language == settings.language)
+                    .unwrap_or(0);
+                settings.language = LANGUAGES[step_index(index, step, LANGUAGES.len())];
+            }
+        }
+    }
+}
+
+impl Plugin for SettingsPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(Settings::load()).add_systems(
+            Update,
+            (
+                handle_setting_input,
+                update_setting_texts,
+                apply_settings,
+                save_settings,
+            )
+                .chain(),
+        );
+    }
+}
+
+/// Spawns one menu item per setting, ordered from `first_order`.
+pub fn spawn_setting_rows(content: &mut ChildSpawnerCommands, first_order: usize) {
+    for (index, field) in SETTING_FIELDS.iter().enumerate() {
+        content.spawn((
+            MenuItem(first_order + index),
+            *field,
+            Node {
+                width: px(340),
+                padding: UiRect::axes(px(16), px(8)),
+                margin: UiRect::vertical(px(3)),
+                justify_content: JustifyContent::Center,
+                ..default()
+            },
+            BackgroundColor(MENU_ITEM_COLOR),
+            children![(
+                Text::default(),
+                TextFont {
+                    font_size: 18.,
+                    ..default()
+                },
+                SettingText(*field),
+            )],
+        ));
+    }
+}
+
+/// Left/right step a setting, confirming steps it forward and wraps around.
+fn handle_setting_input(
+    mut activations: MessageReader<MenuActivated>,
+    mut adjustments: MessageReader<MenuAdjusted>,
+    fields: Query<&SettingField>,
+    mut settings: ResMut<Settings>,
+) {
+    for activation in activations.read() {
+        if let Ok(field) = fields.get(activation.item) {
+            field.adjust(&mut settings, 1, true);
+        }
+    }
+    for adjustment in adjustments.read() {
+        if let Ok(field) = fields.get(adjustment.item) {
+            field.adjust(&mut settings, adjustment.step, false);
+        }
+    }
+}
+
+fn update_setting_texts(
+    settings: Res<Settings>,
+    locale: Res<Locale>,
+    mut texts: Query<(&mut Text, Ref<SettingText>)>,
+) {
+    for (mut text, field) in &mut texts {
+        if settings.is_changed() || locale.is_changed() || field.is_added() {
+            text.0 = field.0.describe(&settings, &locale);
+        }
+    }
+}
+
+fn apply_settings(
+    settings: Res<Settings>,
+    mut window: Single<&mut Window, With<PrimaryWindow>>,
+    mut ui_scale: ResMut<UiScale>,
+    mut global_volume: ResMut<GlobalVolume>,
+) {
+    if !settings.is_changed() {
+        return;
+    }
+
+    let mode = match settings.window_mode {
+        WindowSetting::Windowed => WindowMode::Windowed,
+        WindowSetting::Borderless => WindowMode::BorderlessFullscreen(MonitorSelection::Current),
+        WindowSetting::Fullscreen => {
+            WindowMode::Fullscreen(MonitorSelection::Current, VideoModeSelection::Current)
+        }
+    };
+    if window.mode != mode {
+        window.mode = mode;
+    }
+    if settings.window_mode == WindowSetting::Windowed {
+        let (width, height) = settings.resolution;
+        if window.resolution.width() != width as f32 || window.resolution.height() != height as f32
+        {
+            window.resolution.set(width as f32, height as f32);
+        }
+    }
+    let present_mode = if settings.vsync {
+        PresentMode::AutoVsync
+    } else {
+        PresentMode::AutoNoVsync
+    };
+    if window.present_mode != present_mode {
+        window.present_mode = present_mode;
+    }
+
+    ui_scale.0 = settings.ui_scale;
+    global_volume.volume = Volume::Linear(settings.master_volume);
+}
+
+fn save_settings(settings: Res<Settings>) {
+    if settings.is_changed() && !settings.is_added() {
+        settings.save();
+    }
+}
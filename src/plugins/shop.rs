@@ -0,0 +1,293 @@
+use bevy::{ecs::system::SystemParam, prelude::*};
+
+use crate::plugins::{
+    locale::{Locale, Localized},
+    menu::{MENU_ITEM_COLOR, MenuActivated, MenuFocus, MenuItem},
+    net::{NetRequest, NetRole},
+    player::{Player, PlayerGamepad},
+    upgrade::{PurchaseError, UPGRADES, UpgradeDef, UpgradeId, UpgradePurchased},
+    wave::{GamePhase, Wave},
+};
+
+pub struct ShopPlugin;
+
+#[derive(Component)]
+enum ShopAction {
+    Buy(UpgradeId),
+    StartWave,
+}
+
+#[derive(Component)]
+struct ShopRowText(UpgradeId);
+
+#[derive(Component)]
+struct ShopCoinText;
+
+#[derive(Component)]
+struct ShopFeedback(Timer);
+
+const FEEDBACK_DURATION: f32 = 1.5;
+
+impl Plugin for ShopPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnEnter(GamePhase::Shop), spawn_shop)
+            .add_systems(
+                Update,
+                (handle_shop_actions, update_shop_texts, fade_shop_feedback)
+                    .chain()
+                    .run_if(in_state(GamePhase::Shop)),
+            );
+    }
+}
+
+/// Player shopping with `gamepad`, or the first seated player for mouse input.
+fn shopper<'a>(
+    players: impl Iterator<Item = (Entity, &'a Player, Option<&'a PlayerGamepad>)>,
+    gamepad: Option<Entity>,
+) -> Option<Entity> {
+    let players: Vec<_> = players.collect();
+    players
+        .iter()
+        .find(|(_, _, bound)| bound.is_some_and(|bound| Some(bound.0) == gamepad))
+        .or_else(|| players.iter().min_by_key(|(_, player, _)| player.number))
+        .map(|(entity, _, _)| *entity)
+}
+
+fn row_label(def: &UpgradeDef, player: &Player, locale: &Locale) -> String {
+    let level = player.upgrades.level(def.id);
+    let name = locale.get(def.name_key);
+    let level = match def.max_level {
+        Some(max) => locale.format("shop.level_of", &[("level", &level), ("max", &max)]),
+        None => locale.format("shop.level", &[("level", &level)]),
+    };
+    match def.cost(player.upgrades.level(def.id)) {
+        Some(cost) => locale.format(
+            "shop.row",
+            &[
+                ("name", &name),
+                ("level", &level),
+                ("preview", &def.preview(&player.upgrades, locale)),
+                ("cost", &cost),
+            ],
+        ),
+        None => locale.format("shop.row_maxed", &[("name", &name), ("level", &level)]),
+    }
+}
+
+fn shop_button(order: usize, action: ShopAction, text: impl Bundle) -> impl Bundle {
+    (
+        MenuItem(order),
+        action,
+        Node {
+            width: px(520),
+            padding: UiRect::axes(px(16), px(10)),
+            margin: UiRect::vertical(px(4)),
+            ..default()
+        },
+        BackgroundColor(MENU_ITEM_COLOR),
+        children![text],
+    )
+}
+
+fn spawn_shop(mut commands: Commands, wave: Res<Wave>) {
+    let rows: Vec<_> = UPGRADES
+        .iter()
+        .enumerate()
+        .map(|(order, def)| {
+            shop_button(
+                order,
+                ShopAction::Buy(def.id),
+                (
+                    Text::default(),
+                    TextFont {
+                        font_size: 16.,
+                        ..default()
+                    },
+                    ShopRowText(def.id),
+                ),
+            )
+        })
+        .collect();
+
+    commands.spawn((
+        DespawnOnExit(GamePhase::Shop),
+        Node {
+            position_type: PositionType::Absolute,
+            width: percent(100),
+            height: percent(100),
+            display: Display::Flex,
+            flex_direction: FlexDirection::Column,
+            justify_content: JustifyContent::Center,
+            align_items: AlignItems::Center,
+            ..default()
+        },
+        BackgroundColor(Color::srgba_u8(0, 0, 0, 200)),
+        children![
+            (
+                Localized::new("shop.wave_cleared").with("wave", wave.number),
+                TextFont {
+                    font_size: 32.,
+                    ..default()
+                },
+            ),
+            (
+                Text::default(),
+                TextFont {
+                    font_size: 20.,
+                    ..default()
+                },
+                Node {
+                    margin: UiRect::vertical(px(12)),
+                    ..default()
+                },
+                ShopCoinText
+            ),
+            (
+                Node {
+                    display: Display::Flex,
+                    flex_direction: FlexDirection::Column,
+                    ..default()
+                },
+                Children::spawn(SpawnIter(rows.into_iter()))
+            ),
+            (
+                Text::new(""),
+                TextFont {
+                    font_size: 16.,
+                    ..default()
+                },
+                TextColor(Color::srgb_u8(255, 80, 80)),
+                Node {
+                    height: px(24),
+                    margin: UiRect::vertical(px(8)),
+                    ..default()
+                },
+                ShopFeedback(Timer::from_seconds(FEEDBACK_DURATION, TimerMode::Once))
+            ),
+            shop_button(
+                UPGRADES.len(),
+                ShopAction::StartWave,
+                (
+                    Localized::new("shop.start_wave").with("wave", wave.number + 1),
+                    TextFont {
+                        font_size: 16.,
+                        ..default()
+                    },
+                )
+            )
+        ],
+    ));
+}
+
+/// Carries out shop actions, or forwards them to the host on a client.
+#[derive(SystemParam)]
+struct ShopOrders<'w> {
+    role: Res<'w, NetRole>,
+    purchases: MessageWriter<'w, UpgradePurchased>,
+    requests: MessageWriter<'w, NetRequest>,
+    next_phase: ResMut<'w, NextState<GamePhase>>,
+}
+
+impl ShopOrders<'_> {
+    fn buy(
+        &mut self,
+        entity: Entity,
+        player: &mut Player,
+        id: UpgradeId,
+    ) -> Result<(), PurchaseError> {
+        let def = UpgradeDef::get(id);
+        // Clients only check affordability, the host does the actual purchase.
+        if self.role.is_client() {
+            player.purchase_cost(def)?;
+            self.requests.write(NetRequest::Purchase(id));
+        } else {
+            let cost = player.purchase(def)?;
+            self.purchases.write(UpgradePurchased {
+                player: entity,
+                id,
+                cost,
+            });
+        }
+        Ok(())
+    }
+
+    fn start_wave(&mut self) {
+        if self.role.is_client() {
+            self.requests.write(NetRequest::StartWave);
+        } else {
+            self.next_phase.set(GamePhase::Combat);
+        }
+    }
+}
+
+fn handle_shop_actions(
+    mut activations: MessageReader<MenuActivated>,
+    actions: Query<&ShopAction>,
+    mut player_query: Query<(Entity, &mut Player, Option<&PlayerGamepad>)>,
+    mut feedback: Single<(&mut Text, &mut ShopFeedback)>,
+    mut orders: ShopOrders,
+    locale: Res<Locale>,
+) {
+    let (feedback_text, feedback_timer) = &mut *feedback;
+
+    for activation in activations.read() {
+        match actions.get(activation.item) {
+            Ok(ShopAction::Buy(id)) => {
+                let Some(entity) = shopper(player_query.iter(), activation.gamepad) else {
+                    continue;
+                };
+                let Ok((_, mut player, _)) = player_query.get_mut(entity) else {
+                    continue;
+                };
+                let message = match orders.buy(entity, &mut player, *id) {
+                    Ok(()) => continue,
+                    Err(PurchaseError::MaxLevel) => locale.get("shop.max_level"),
+                    Err(PurchaseError::NotEnoughCoins { cost }) => locale.format(
+                        "shop.not_enough_coins",
+                        &[("coins", &player.coins), ("cost", &cost)],
+                    ),
+                };
+                feedback_text.0 = message;
+                feedback_timer.0.reset();
+            }
+            Ok(ShopAction::StartWave) => orders.start_wave(),
+            Err(_) => {}
+        }
+    }
+}
+
+fn update_shop_texts(
+    players: Query<(Entity, Ref<Player>, Option<&PlayerGamepad>)>,
+    focus: Res<MenuFocus>,
+    locale: Res<Locale>,
+    opened: Query<(), Added<ShopCoinText>>,
+    mut rows: Query<(&mut Text, &ShopRowText), Without<ShopCoinText>>,
+    mut coins: Single<&mut Text, With<ShopCoinText>>,
+) {
+    let shopping = shopper(
+        players
+            .iter()
+            .map(|(entity, player, gamepad)| (entity, player.into_inner(), gamepad)),
+        focus.gamepad,
+    );
+    let Some((_, player, _)) = shopping.and_then(|entity| players.get(entity).ok()) else {
+        return;
+    };
+    if !player.is_changed() && !focus.is_changed() && !locale.is_changed() && opened.is_empty() {
+        return;
+    }
+    coins.0 = locale.format(
+        "shop.coins",
+        &[("number", &player.number), ("coins", &player.coins)],
+    );
+    for (mut text, ShopRowText(id)) in &mut rows {
+        text.0 = row_label(UpgradeDef::get(*id), &player, &locale);
+    }
+}
+
+fn fade_shop_feedback(time: Res<Time>, mut feedback: Single<(&mut Text, &mut ShopFeedback)>) {
+    let (text, timer) = &mut *feedback;
+    if timer.0.tick(time.delta()).just_finished() {
+        text.0.clear();
+    }
+}
@@ -0,0 +1,124 @@
+use std::collections::HashMap;
+
+use bevy::{audio::Volume, prelude::*};
+use rand::Rng;
+
+use crate::plugins::settings::Settings;
+
+pub struct SoundPlugin;
+
+/// Mixer channel a sound plays on, each with its own volume setting.
+#[derive(Component, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum AudioBus {
+    Sfx,
+    Music,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum Sfx {
+    Shot,
+    EnemyDie,
+    LevelUp,
+}
+
+const SFX: &[Sfx] = &[Sfx::Shot, Sfx::EnemyDie, Sfx::LevelUp];
+
+impl Sfx {
+    fn path(self) -> &'static str {
+        match self {
+            Sfx::Shot => "impact.ogg",
+            Sfx::EnemyDie => "die.ogg",
+            Sfx::LevelUp => "level.ogg",
+        }
+    }
+
+    /// Voices of this sound allowed at once, further requests are dropped until one ends.
+    fn max_voices(self) -> usize {
+        match self {
+            Sfx::Shot => 4,
+            Sfx::EnemyDie => 6,
+            Sfx::LevelUp => 2,
+        }
+    }
+
+    /// Largest relative change of playback speed, so repeated sounds do not sound identical.
+    fn pitch_variation(self) -> f32 {
+        match self {
+            Sfx::Shot => 0.08,
+            Sfx::EnemyDie => 0.1,
+            Sfx::LevelUp => 0.03,
+        }
+    }
+}
+
+/// Asks for a sound effect to be played on the SFX bus.
+#[derive(Message)]
+pub struct PlaySfx(pub Sfx);
+
+#[derive(Resource)]
+struct SfxLibrary(HashMap<Sfx, Handle<AudioSource>>);
+
+/// A playing sound effect, despawned by Bevy once it finishes.
+#[derive(Component)]
+struct SfxVoice(Sfx);
+
+impl Plugin for SoundPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_message::<PlaySfx>()
+            .add_systems(Startup, load_sfx)
+            .add_systems(Update, apply_bus_volumes)
+            .add_systems(PostUpdate, play_sfx);
+    }
+}
+
+fn load_sfx(asset_server: Res<AssetServer>, mut commands: Commands) {
+    commands.insert_resource(SfxLibrary(
+        SFX.iter()
+            .map(|sfx| (*sfx, asset_server.load(sfx.path())))
+            .collect(),
+    ));
+}
+
+fn play_sfx(
+    mut requests: MessageReader<PlaySfx>,
+    library: Res<SfxLibrary>,
+    settings: Res<Settings>,
+    voices: Query<&SfxVoice>,
+    mut commands: Commands,
+) {
+    let mut playing: HashMap<Sfx, usize> = HashMap::new();
+    for SfxVoice(sfx) in &voices {
+        *playing.entry(*sfx).or_default() += 1;
+    }
+
+    let mut rng = rand::rng();
+    for PlaySfx(sfx) in requests.read() {
+        let count = playing.entry(*sfx).or_default();
+        if *count >= sfx.max_voices() {
+            continue;
+        }
+        *count += 1;
+
+        let variation = sfx.pitch_variation();
+        commands.spawn((
+            SfxVoice(*sfx),
+            AudioBus::Sfx,
+            AudioPlayer::new(library.0[sfx].clone()),
+            PlaybackSettings::DESPAWN
+                .with_volume(settings.bus_volume(AudioBus::Sfx))
+                .with_speed(1. + rng.random_range(-variation..=variation)),
+        ));
+    }
+}
+
+/// Keeps sounds already playing in line with the volume settings.
+fn apply_bus_volumes(settings: Res<Settings>, mut sinks: Query<(&mut AudioSink, &AudioBus)>) {
+    if !settings.is_changed() {
+        return;
+    }
+    // The global volume only applies to sounds started after it changes.
+    let master = Volume::Linear(settings.master_volume);
+    for (mut sink, bus) in &mut sinks {
+        sink.set_volume(settings.bus_volume(*bus) * master);
+    }
+}
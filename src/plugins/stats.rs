@@ -0,0 +1,80 @@
+use std::collections::HashMap;
+
+use bevy::prelude::*;
+
+use crate::{
+    AppState,
+    plugins::{
+        enemy::{EnemyKilled, EnemyKind},
+        upgrade::UpgradePurchased,
+        wave::GamePhase,
+    },
+};
+
+pub struct StatsPlugin;
+
+/// Totals of the current run, shared by every player.
+#[derive(Resource, Default, Debug, Clone, PartialEq)]
+pub struct RunStats {
+    pub waves_cleared: u32,
+    pub kills: u32,
+    pub kills_by_kind: HashMap<EnemyKind, u32>,
+    pub shots_fired: u32,
+    /// Bullets that hit an enemy still alive.
+    pub hits: u32,
+    pub damage_dealt: f32,
+    pub coins_earned: u32,
+    pub coins_spent: u32,
+    pub upgrades_bought: u32,
+    pub time_alive: f32,
+}
+
+impl RunStats {
+    pub fn score(&self) -> u32 {
+        self.waves_cleared * 100 + self.kills * 10 + self.coins_earned + self.time_alive as u32
+    }
+
+    /// Share of shots that hit, `None` before the first shot.
+    pub fn accuracy(&self) -> Option<f32> {
+        (self.shots_fired > 0).then(|| self.hits as f32 / self.shots_fired as f32)
+    }
+}
+
+impl Plugin for StatsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<RunStats>()
+            .add_systems(OnEnter(AppState::InGame), reset_stats)
+            .add_systems(OnEnter(GamePhase::Shop), count_cleared_wave)
+            .add_systems(
+                Update,
+                (count_kills, count_purchases, tick_time_alive).run_if(in_state(AppState::InGame)),
+            );
+    }
+}
+
+fn reset_stats(mut stats: ResMut<RunStats>) {
+    *stats = RunStats::default();
+}
+
+fn count_cleared_wave(mut stats: ResMut<RunStats>) {
+    stats.waves_cleared += 1;
+}
+
+/// Bomb victims count too, the stats screen says so.
+fn count_kills(mut kills: MessageReader<EnemyKilled>, mut stats: ResMut<RunStats>) {
+    for kill in kills.read() {
+        stats.kills += 1;
+        *stats.kills_by_kind.entry(kill.kind).or_default() += 1;
+    }
+}
+
+fn count_purchases(mut purchases: MessageReader<UpgradePurchased>, mut stats: ResMut<RunStats>) {
+    for purchase in purchases.read() {
+        stats.upgrades_bought += 1;
+        stats.coins_spent += purchase.cost;
+    }
+}
+
+fn tick_time_alive(mut stats: ResMut<RunStats>, time: Res<Time>) {
+    stats.time_alive += time.delta_secs();
+}
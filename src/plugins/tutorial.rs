@@ -0,0 +1,288 @@
+use bevy::prelude::*;
+
+use crate::{
+    AppState,
+    plugins::{
+        accessibility::ContrastOutline,
+        endless::GameMode,
+        enemy::{Enemy, EnemyKilled, EnemyKind, EnemySpawner},
+        glyphs::{Glyph, GlyphIcon},
+        locale::Locale,
+        net::SimulationSystems,
+        player::{Player, PlayerInput, ReadPlayerInput},
+        ui::HudElement,
+        upgrade::{UPGRADES, UpgradePurchased},
+        wave::GamePhase,
+    },
+};
+
+pub struct TutorialPlugin;
+
+/// What the player has to do to get past a tutorial step.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum TutorialGoal {
+    Move,
+    Aim,
+    ShootEnemy,
+    EarnCoins(u32),
+    BuyUpgrade,
+}
+
+/// The tutorial script, played in order during the first wave.
+const TUTORIAL_STEPS: &[TutorialGoal] = &[
+    TutorialGoal::Move,
+    TutorialGoal::Aim,
+    TutorialGoal::ShootEnemy,
+    TutorialGoal::EarnCoins(10),
+    TutorialGoal::BuyUpgrade,
+];
+
+/// Stick deflection that counts as moving or aiming on purpose.
+const STICK_THRESHOLD: f32 = 0.5;
+
+impl TutorialGoal {
+    fn prompt(self, locale: &Locale) -> String {
+        match self {
+            TutorialGoal::Move => locale.get("tutorial.move"),
+            TutorialGoal::Aim => locale.get("tutorial.aim"),
+            TutorialGoal::ShootEnemy => locale.get("tutorial.shoot"),
+            TutorialGoal::EarnCoins(coins) => {
+                locale.format("tutorial.earn_coins", &[("count", &coins)])
+            }
+            TutorialGoal::BuyUpgrade => locale.get("tutorial.buy_upgrade"),
+        }
+    }
+
+    fn glyphs(self) -> Vec<Glyph> {
+        match self {
+            TutorialGoal::Move | TutorialGoal::EarnCoins(_) => vec![Glyph::LeftStick],
+            TutorialGoal::Aim => vec![Glyph::RightStick],
+            TutorialGoal::ShootEnemy => {
+                vec![
+                    Glyph::RightStick,
+                    Glyph::Button(GamepadButton::RightTrigger2),
+                ]
+            }
+            TutorialGoal::BuyUpgrade => UPGRADES
+                .iter()
+                .filter_map(|def| def.button.map(Glyph::Button))
+                .collect(),
+        }
+    }
+
+    fn highlight(self) -> Option<HudElement> {
+        match self {
+            TutorialGoal::EarnCoins(_) => Some(HudElement::Coins),
+            TutorialGoal::BuyUpgrade => Some(HudElement::Upgrades),
+            _ => None,
+        }
+    }
+
+    /// Whether enemies are sent in while this step is current.
+    fn needs_enemies(self) -> bool {
+        matches!(self, TutorialGoal::ShootEnemy | TutorialGoal::EarnCoins(_))
+    }
+}
+
+/// Index of the current step in [`TUTORIAL_STEPS`], `None` outside of the tutorial.
+#[derive(Resource, Default)]
+pub struct TutorialProgress {
+    step: Option<usize>,
+}
+
+impl TutorialProgress {
+    fn goal(&self) -> Option<TutorialGoal> {
+        TUTORIAL_STEPS.get(self.step?).copied()
+    }
+}
+
+/// The wave cannot end while this holds, spawning is left to the script.
+pub fn tutorial_in_progress(progress: Res<TutorialProgress>) -> bool {
+    progress.step.is_some()
+}
+
+/// Row holding the step count, the glyphs of the current step and its prompt.
+#[derive(Component)]
+struct TutorialPrompt;
+
+const HIGHLIGHT_COLOR: Color = Color::srgb_u8(255, 220, 60);
+
+impl Plugin for TutorialPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<TutorialProgress>()
+            .add_systems(OnEnter(AppState::InGame), start_tutorial)
+            .add_systems(OnEnter(GamePhase::Combat), spawn_tutorial_prompt)
+            .add_systems(
+                Update,
+                (advance_tutorial, feed_tutorial_enemies)
+                    .chain()
+                    .after(ReadPlayerInput)
+                    .in_set(SimulationSystems)
+                    .run_if(in_state(GamePhase::Combat))
+                    .run_if(tutorial_in_progress),
+            )
+            .add_systems(
+                Update,
+                (update_tutorial_prompt, highlight_hud_elements).run_if(in_state(AppState::InGame)),
+            );
+    }
+}
+
+fn start_tutorial(mode: Res<GameMode>, mut progress: ResMut<TutorialProgress>) {
+    progress.step = (*mode == GameMode::Tutorial).then_some(0);
+}
+
+fn spawn_tutorial_prompt(progress: Res<TutorialProgress>, mut commands: Commands) {
+    if progress.step.is_none() {
+        return;
+    }
+    commands.spawn((
+        DespawnOnExit(GamePhase::Combat),
+        Node {
+            position_type: PositionType::Absolute,
+            top: px(130),
+            width: percent(100),
+            justify_content: JustifyContent::Center,
+            ..default()
+        },
+        children![(
+            TutorialPrompt,
+            Node {
+                display: Display::Flex,
+                align_items: AlignItems::Center,
+                column_gap: px(10),
+                padding: UiRect::axes(px(16), px(8)),
+                ..default()
+            },
+            BackgroundColor(Color::srgba_u8(0, 0, 0, 180)),
+            ContrastOutline,
+        )],
+    ));
+}
+
+fn advance_tutorial(
+    mut progress: ResMut<TutorialProgress>,
+    inputs: Query<&PlayerInput>,
+    players: Query<&Player>,
+    mut kills: MessageReader<EnemyKilled>,
+    mut purchases: MessageReader<UpgradePurchased>,
+) {
+    let killed = kills.read().count() > 0;
+    let purchased = purchases.read().count() > 0;
+    let Some(goal) = progress.goal() else {
+        return;
+    };
+
+    let done = match goal {
+        TutorialGoal::Move => inputs
+            .iter()
+            .any(|input| input.movement.length() > STICK_THRESHOLD),
+        TutorialGoal::Aim => inputs
+            .iter()
+            .any(|input| input.aim.length() > STICK_THRESHOLD),
+        TutorialGoal::ShootEnemy => killed,
+        TutorialGoal::EarnCoins(coins) => players.iter().any(|player| player.coins >= coins),
+        TutorialGoal::BuyUpgrade => purchased,
+    };
+    if done {
+        progress.step = progress
+            .step
+            .map(|step| step + 1)
+            .filter(|step| *step < TUTORIAL_STEPS.len());
+    }
+}
+
+/// Sends one enemy at a time, from the quickest spawner, while the step calls for it.
+fn feed_tutorial_enemies(
+    progress: Res<TutorialProgress>,
+    enemies: Query<(), With<Enemy>>,
+    mut spawners: Query<&mut EnemySpawner>,
+) {
+    if !progress.goal().is_some_and(TutorialGoal::needs_enemies)
+        || !enemies.is_empty()
+        || spawners.iter().any(|spawner| !spawner.queue.is_empty())
+    {
+        return;
+    }
+    if let Some(mut spawner) = spawners
+        .iter_mut()
+        .min_by(|a, b| a.interval.total_cmp(&b.interval))
+    {
+        spawner.queue.push_back(EnemyKind::Grunt);
+    }
+}
+
+fn prompt_text(text: String) -> impl Bundle {
+    (
+        Text::new(text),
+        TextFont {
+            font_size: 24.,
+            ..default()
+        },
+        TextColor(HIGHLIGHT_COLOR),
+    )
+}
+
+fn update_tutorial_prompt(
+    progress: Res<TutorialProgress>,
+    locale: Res<Locale>,
+    prompt: Query<(Entity, Ref<TutorialPrompt>)>,
+    mut commands: Commands,
+) {
+    let Ok((entity, prompt)) = prompt.single() else {
+        return;
+    };
+    if !progress.is_changed() && !locale.is_changed() && !prompt.is_added() {
+        return;
+    }
+    let mut prompt = commands.entity(entity);
+    prompt.despawn_related::<Children>();
+    match (progress.step, progress.goal()) {
+        (Some(step), Some(goal)) => {
+            prompt.with_children(|prompt| {
+                prompt.spawn(prompt_text(format!(
+                    "{}/{}",
+                    step + 1,
+                    TUTORIAL_STEPS.len()
+                )));
+                for glyph in goal.glyphs() {
+                    prompt.spawn((
+                        GlyphIcon(glyph),
+                        Node {
+                            min_width: px(32),
+                            height: px(32),
+                            padding: UiRect::horizontal(px(4)),
+                            ..default()
+                        },
+                    ));
+                }
+                prompt.spawn(prompt_text(goal.prompt(&locale)));
+            });
+        }
+        _ => {
+            prompt.with_child(prompt_text(locale.get("tutorial.complete")));
+        }
+    }
+}
+
+/// Outlines the HUD element the current step is about.
+fn highlight_hud_elements(
+    progress: Res<TutorialProgress>,
+    elements: Query<(Entity, &HudElement, Has<Outline>)>,
+    mut commands: Commands,
+) {
+    let highlighted = progress.goal().and_then(TutorialGoal::highlight);
+    for (entity, element, outlined) in &elements {
+        match (highlighted == Some(*element), outlined) {
+            (true, false) => {
+                commands
+                    .entity(entity)
+                    .insert(Outline::new(px(3), px(2), HIGHLIGHT_COLOR));
+            }
+            (false, true) => {
+                commands.entity(entity).remove::<Outline>();
+            }
+            _ => {}
+        }
+    }
+}
@@ -1,62 +1,420 @@
 use bevy::prelude::*;
 
-use crate::plugins::player::Player;
+use crate::{
+    AppState, Cpu,
+    plugins::{
+        accessibility::{ContrastOutline, Painted, Swatch},
+        binding::{Binding, update_bindings},
+        glyphs::{Glyph, GlyphIcon},
+        health::Health,
+        locale::{Locale, Localized},
+        player::Player,
+        powerup::{ActiveBuffs, POWER_UPS, PowerUpKind},
+        upgrade::{UPGRADES, UpgradeDef, UpgradeEffect},
+        wave::{GamePhase, Wave, WaveCountdown, WaveEnemies, spawn_wave_progress},
+    },
+};
 
 pub struct GameUiPlugin;
 
 #[derive(Component)]
-struct PlayerCoinText;
+struct WaveText;
 
+/// "Wave N incoming" shown when a wave starts, fading out until its timer ends.
 #[derive(Component)]
-pub struct CpuHealthBar;
+struct WaveBanner(Timer);
+
+const WAVE_BANNER_SECS: f32 = 2.5;
+
+/// Parts of the HUD other plugins can point at, such as the tutorial.
+#[derive(Component, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum HudElement {
+    Coins,
+    Upgrades,
+}
+
+/// Row at the bottom of the screen holding one panel per player.
+#[derive(Component)]
+struct PlayerPanels;
 
 #[derive(Component)]
-struct FireRateLevelText;
+struct PlayerPanel(Entity);
 
 #[derive(Component)]
-struct DamageLevelText;
+struct BuffIndicator {
+    player: Entity,
+    kind: PowerUpKind,
+}
 
 #[derive(Component)]
-struct SpeedLevelText;
+struct BuffIndicatorText {
+    player: Entity,
+    kind: PowerUpKind,
+}
+
+#[derive(Component)]
+struct BuffIndicatorBar {
+    player: Entity,
+    kind: PowerUpKind,
+}
 
 impl Plugin for GameUiPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(Startup, spawn_ui)
-            .add_systems(FixedUpdate, (update_player_coin_ui, update_upgrade_levels));
+        app.add_systems(
+            OnEnter(AppState::InGame),
+            spawn_ui.after(crate::setup).after(spawn_wave_progress),
+        )
+        .add_systems(Update, (spawn_player_panels, despawn_player_panels))
+        .add_systems(
+            PostUpdate,
+            (
+                update_bindings::<Player>,
+                update_bindings::<Health>,
+                update_bindings::<WaveEnemies>,
+                update_bindings::<WaveCountdown>,
+            ),
+        )
+        .add_systems(OnEnter(GamePhase::Combat), spawn_wave_banner)
+        .add_systems(Update, (update_buff_indicators, update_wave_text))
+        .add_systems(Update, fade_wave_banner.run_if(in_state(AppState::InGame)));
+    }
+}
+
+fn update_wave_text(wave: Res<Wave>, mut texts: Query<&mut Localized, With<WaveText>>) {
+    if !wave.is_changed() {
+        return;
+    }
+    for mut text in &mut texts {
+        *text = Localized::new("hud.wave").with("wave", wave.number);
+    }
+}
+
+fn spawn_wave_banner(
+    wave: Res<Wave>,
+    banners: Query<Entity, With<WaveBanner>>,
+    mut commands: Commands,
+) {
+    for banner in &banners {
+        commands.entity(banner).despawn();
+    }
+    commands.spawn((
+        DespawnOnExit(AppState::InGame),
+        WaveBanner(Timer::from_seconds(WAVE_BANNER_SECS, TimerMode::Once)),
+        Node {
+            position_type: PositionType::Absolute,
+            width: percent(100),
+            top: percent(30),
+            justify_content: JustifyContent::Center,
+            ..default()
+        },
+        children![(
+            Localized::new("hud.wave_incoming").with("wave", wave.number),
+            TextFont {
+                font_size: 48.,
+                ..default()
+            },
+            TextColor(Color::WHITE),
+        )],
+    ));
+}
+
+fn fade_wave_banner(
+    time: Res<Time>,
+    mut banners: Query<(Entity, &mut WaveBanner, &Children)>,
+    mut colors: Query<&mut TextColor>,
+    mut commands: Commands,
+) {
+    for (entity, mut banner, children) in &mut banners {
+        if banner.0.tick(time.delta()).is_finished() {
+            commands.entity(entity).despawn();
+            continue;
+        }
+        // Fully visible for the first half, then fading out.
+        let alpha = (banner.0.fraction_remaining() * 2.).min(1.);
+        for child in children {
+            if let Ok(mut color) = colors.get_mut(*child) {
+                color.0.set_alpha(alpha);
+            }
+        }
     }
 }
 
-fn update_player_coin_ui(
-    mut text: Single<&mut Text, With<PlayerCoinText>>,
-    player: Query<&Player, Changed<Player>>,
+fn update_buff_indicators(
+    locale: Res<Locale>,
+    buffs: Query<&ActiveBuffs, With<Player>>,
+    mut indicators: Query<(&BuffIndicator, &mut Node), Without<BuffIndicatorBar>>,
+    mut bars: Query<(&BuffIndicatorBar, &mut Node), Without<BuffIndicator>>,
+    mut texts: Query<(&BuffIndicatorText, &mut Text)>,
 ) {
-    if let Ok(player) = player.single() {
-        text.0 = format!("coins: {}", player.coins);
+    for (BuffIndicator { player, kind }, mut node) in &mut indicators {
+        let display = if buffs.get(*player).is_ok_and(|buffs| buffs.has(*kind)) {
+            Display::Flex
+        } else {
+            Display::None
+        };
+        if node.display != display {
+            node.display = display;
+        }
     }
+
+    for (BuffIndicatorBar { player, kind }, mut node) in &mut bars {
+        if let Some(timer) = buffs
+            .get(*player)
+            .ok()
+            .and_then(|buffs| buffs.remaining(*kind))
+        {
+            node.width = percent(timer.fraction_remaining() * 100.);
+        }
+    }
+
+    for (BuffIndicatorText { player, kind }, mut text) in &mut texts {
+        if let Some(timer) = buffs
+            .get(*player)
+            .ok()
+            .and_then(|buffs| buffs.remaining(*kind))
+        {
+            text.0 = locale.format(
+                "hud.buff",
+                &[
+                    ("buff", &locale.get(kind.key())),
+                    ("seconds", &format!("{:.1}", timer.remaining_secs())),
+                ],
+            );
+        }
+    }
+}
+
+fn buff_indicator(player: Entity, kind: PowerUpKind) -> impl Bundle {
+    (
+        BuffIndicator { player, kind },
+        Node {
+            display: Display::None,
+            flex_direction: FlexDirection::Column,
+            width: px(140),
+            margin: UiRect::bottom(px(6)),
+            ..default()
+        },
+        children![
+            (
+                Text::default(),
+                TextFont {
+                    font_size: 12.,
+                    ..default()
+                },
+                Painted::Text(Swatch::PowerUp(kind)),
+                BuffIndicatorText { player, kind }
+            ),
+            (
+                Node {
+                    width: percent(100),
+                    height: px(6),
+                    ..default()
+                },
+                BackgroundColor(Color::srgb_u8(50, 50, 50)),
+                ContrastOutline,
+                children![(
+                    Node {
+                        width: percent(100),
+                        height: percent(100),
+                        ..default()
+                    },
+                    Painted::Background(Swatch::PowerUp(kind)),
+                    BuffIndicatorBar { player, kind }
+                )]
+            )
+        ],
+    )
 }
 
-fn update_upgrade_levels(
-    player: Query<&Player, Changed<Player>>,
-    mut fire_rate_text: Single<&mut Text, With<FireRateLevelText>>,
-    mut damage_text: Single<&mut Text, (With<DamageLevelText>, Without<FireRateLevelText>)>,
-    mut speed_text: Single<
-        &mut Text,
-        (
-            With<SpeedLevelText>,
-            Without<FireRateLevelText>,
-            Without<DamageLevelText>,
+const RADIAL_SIZE: f32 = 200.;
+const RADIAL_ITEM_OFFSET: f32 = 60.;
+const RADIAL_ITEM_WIDTH: f32 = 80.;
+const RADIAL_ITEM_HEIGHT: f32 = 70.;
+
+/// Direction of a face button from the center of the radial HUD, in UI space.
+fn radial_direction(button: GamepadButton) -> Option<Vec2> {
+    match button {
+        GamepadButton::West => Some(Vec2::NEG_X),
+        GamepadButton::North => Some(Vec2::NEG_Y),
+        GamepadButton::East => Some(Vec2::X),
+        GamepadButton::South => Some(Vec2::Y),
+        _ => None,
+    }
+}
+
+fn upgrade_label(def: &UpgradeDef, player: &Player, locale: &Locale) -> String {
+    let level = player.upgrades.level(def.id);
+    let name = match def.effect {
+        UpgradeEffect::Stat { .. } => locale.format(
+            "hud.upgrade_level",
+            &[("name", &locale.get(def.name_key)), ("level", &level)],
         ),
-    >,
+        UpgradeEffect::HealCpu(_) => locale.get(def.name_key),
+    };
+    match def.cost(level) {
+        Some(cost) => locale.format("hud.upgrade_cost", &[("name", &name), ("cost", &cost)]),
+        None => locale.format("hud.upgrade_maxed", &[("name", &name)]),
+    }
+}
+
+fn radial_hud(player: Entity) -> impl Bundle {
+    let center = Vec2::splat(RADIAL_SIZE / 2.);
+    let radial_items: Vec<_> = UPGRADES
+        .iter()
+        .filter_map(|def| {
+            let button = def.button?;
+            let id = def.id;
+            let offset = radial_direction(button)? * RADIAL_ITEM_OFFSET;
+            let position = center + offset;
+            Some((
+                Node {
+                    position_type: PositionType::Absolute,
+                    left: px(position.x - RADIAL_ITEM_WIDTH / 2.),
+                    top: px(position.y - RADIAL_ITEM_HEIGHT / 2.),
+                    width: px(RADIAL_ITEM_WIDTH),
+                    height: px(RADIAL_ITEM_HEIGHT),
+                    display: Display::Flex,
+                    flex_direction: FlexDirection::Column,
+                    justify_content: JustifyContent::Center,
+                    align_items: AlignItems::Center,
+                    ..default()
+                },
+                children![
+                    (
+                        GlyphIcon(Glyph::Button(button)),
+                        Node {
+                            width: px(40),
+                            height: px(40),
+                            ..default()
+                        }
+                    ),
+                    (
+                        Text::default(),
+                        TextFont {
+                            font_size: 10.,
+                            ..default()
+                        },
+                        TextLayout::new_with_justify(Justify::Center),
+                        Binding::text(player, move |player: &Player, locale: &Locale| {
+                            upgrade_label(UpgradeDef::get(id), player, locale)
+                        })
+                    )
+                ],
+            ))
+        })
+        .collect();
+
+    (
+        Node {
+            width: px(RADIAL_SIZE),
+            height: px(RADIAL_SIZE),
+            border_radius: BorderRadius::all(percent(100)),
+            ..default()
+        },
+        BackgroundColor(Color::srgb_u8(50, 50, 50)),
+        HudElement::Upgrades,
+        Children::spawn(SpawnIter(radial_items.into_iter())),
+    )
+}
+
+fn spawn_player_panels(
+    players: Query<(Entity, &Player), Added<Player>>,
+    panels: Single<Entity, With<PlayerPanels>>,
+    mut commands: Commands,
 ) {
-    if let Ok(player) = player.single() {
-        fire_rate_text.0 = format!("Fire Rate Lv{}", player.fire_rate_level);
-        damage_text.0 = format!("Damage Lv{}", player.damage_level);
-        speed_text.0 = format!("Speed Lv{}", player.speed_level);
+    for (entity, player) in &players {
+        let color = Swatch::Player(player.number);
+        let buff_indicators: Vec<_> = POWER_UPS
+            .iter()
+            .filter(|kind| kind.duration().is_some())
+            .map(|kind| buff_indicator(entity, *kind))
+            .collect();
+
+        commands.spawn((
+            ChildOf(*panels),
+            PlayerPanel(entity),
+            Node {
+                display: Display::Flex,
+                flex_direction: FlexDirection::Column,
+                padding: UiRect::all(px(8)),
+                border: UiRect::all(px(3)),
+                ..default()
+            },
+            Painted::Border(color),
+            BackgroundColor(Color::srgba_u8(0, 0, 0, 120)),
+            ContrastOutline,
+            children![
+                (
+                    Localized::new("hud.player").with("number", player.number),
+                    TextFont {
+                        font_size: 20.,
+                        ..default()
+                    },
+                    Painted::Text(color),
+                ),
+                (
+                    Text::default(),
+                    HudElement::Coins,
+                    Binding::text(entity, |player: &Player, locale: &Locale| locale.format(
+                        "hud.coins_kills",
+                        &[("coins", &player.coins), ("kills", &player.kills)]
+                    )),
+                    TextFont {
+                        font_size: 16.,
+                        ..default()
+                    },
+                    TextColor(Color::WHITE),
+                    Node {
+                        margin: UiRect::bottom(px(6)),
+                        ..default()
+                    }
+                ),
+                (
+                    Node {
+                        display: Display::Flex,
+                        align_items: AlignItems::End,
+                        column_gap: px(8),
+                        ..default()
+                    },
+                    children![
+                        radial_hud(entity),
+                        (
+                            Node {
+                                display: Display::Flex,
+                                flex_direction: FlexDirection::Column,
+                                ..default()
+                            },
+                            Children::spawn(SpawnIter(buff_indicators.into_iter())),
+                        )
+                    ]
+                )
+            ],
+        ));
+    }
+}
+
+fn despawn_player_panels(
+    mut removed: RemovedComponents<Player>,
+    panels: Query<(Entity, &PlayerPanel)>,
+    mut commands: Commands,
+) {
+    for player in removed.read() {
+        for (entity, PlayerPanel(owner)) in &panels {
+            if *owner == player {
+                commands.entity(entity).despawn();
+            }
+        }
     }
 }
 
-fn spawn_ui(mut commands: Commands, asset_server: ResMut<AssetServer>) {
+fn spawn_ui(
+    mut commands: Commands,
+    wave: Res<Wave>,
+    cpu: Single<Entity, With<Cpu>>,
+    progress: Single<Entity, With<WaveEnemies>>,
+) {
     commands.spawn((
+        DespawnOnExit(AppState::InGame),
         Node {
             width: percent(100),
             height: px(110),
@@ -76,177 +434,78 @@ fn spawn_ui(mut commands: Commands, asset_server: ResMut<AssetServer>) {
                 },
                 BackgroundColor(Color::srgb_u8(50, 50, 50)),
                 BorderColor::all(Color::srgb_u8(240, 240, 240)),
+                ContrastOutline,
                 children![(
                     Node {
                         width: percent(100),
                         height: percent(100),
                         ..default()
                     },
-                    BackgroundColor(Color::srgb_u8(0, 200, 0)),
-                    CpuHealthBar
+                    Painted::Background(Swatch::Cpu),
+                    Binding::width(*cpu, |health: &Health| health.0)
                 )]
             ),
             (
-                Text::new("coins: 0"),
-                PlayerCoinText,
-                TextFont {
-                    font_size: 20.,
-                    ..default()
-                },
-                TextColor(Color::WHITE),
                 Node {
-                    width: px(150),
-                    height: px(40),
-                    ..default()
-                }
-            ),
-            (
-                Node {
-                    width: px(200),
-                    height: px(200),
-                    border_radius: BorderRadius::all(percent(100)),
-
-                    margin: UiRect::top(px(150)).with_right(px(20)),
+                    display: Display::Flex,
+                    flex_direction: FlexDirection::Column,
+                    align_items: AlignItems::End,
                     ..default()
                 },
-                BackgroundColor(Color::srgb_u8(50, 50, 50)),
                 children![
                     (
-                        Node {
-                            width: percent(33),
-                            height: percent(100),
-                            display: Display::Flex,
-                            flex_direction: FlexDirection::Column,
-                            justify_content: JustifyContent::Center,
-                            align_items: AlignItems::Center,
+                        Localized::new("hud.wave").with("wave", wave.number),
+                        TextFont {
+                            font_size: 28.,
                             ..default()
                         },
-                        children![
-                            (
-                                ImageNode::new(asset_server.load("Y.png")),
-                                Node {
-                                    width: px(40),
-                                    height: px(40),
-                                    margin: UiRect::left(px(-60)),
-                                    ..default()
-                                }
-                            ),
-                            (
-                                Text::new("Fire Rate Lv0"),
-                                TextFont {
-                                    font_size: 10.,
-                                    ..default()
-                                },
-                                Node {
-                                    margin: UiRect::left(px(-60)),
-                                    ..default()
-                                },
-                                FireRateLevelText
-                            )
-                        ]
+                        WaveText
                     ),
                     (
-                        Node {
-                            width: percent(33),
-                            height: percent(100),
-                            display: Display::Flex,
-                            flex_direction: FlexDirection::Column,
-                            justify_content: JustifyContent::SpaceBetween,
-                            align_items: AlignItems::Center,
+                        Text::default(),
+                        TextFont {
+                            font_size: 16.,
                             ..default()
                         },
-                        children![
-                            (
-                                Node {
-                                    display: Display::Flex,
-                                    flex_direction: FlexDirection::Column,
-                                    align_items: AlignItems::Center,
-                                    margin: UiRect::top(px(-17)),
-                                    ..default()
-                                },
-                                children![
-                                    (
-                                        ImageNode::new(asset_server.load("X.png")),
-                                        Node {
-                                            width: px(40),
-                                            height: px(40),
-                                            ..default()
-                                        }
-                                    ),
-                                    (
-                                        Text::new("Damage Lv0"),
-                                        TextFont {
-                                            font_size: 10.,
-                                            ..default()
-                                        },
-                                        DamageLevelText
-                                    )
-                                ]
-                            ),
-                            (
-                                Node {
-                                    display: Display::Flex,
-                                    flex_direction: FlexDirection::Column,
-                                    align_items: AlignItems::Center,
-                                    margin: UiRect::bottom(px(-17)),
-                                    ..default()
-                                },
-                                children![
-                                    (
-                                        ImageNode::new(asset_server.load("B.png")),
-                                        Node {
-                                            width: px(40),
-                                            height: px(40),
-                                            ..default()
-                                        }
-                                    ),
-                                    (
-                                        Text::new("Speed Lv0"),
-                                        TextFont {
-                                            font_size: 10.,
-                                            ..default()
-                                        },
-                                        SpeedLevelText
-                                    )
-                                ]
-                            ),
-                        ]
+                        Binding::text(*progress, |enemies: &WaveEnemies, locale: &Locale| {
+                            locale.format("hud.enemies_left", &[("count", &enemies.0)])
+                        })
                     ),
                     (
-                        Node {
-                            width: percent(33),
-                            height: percent(100),
-                            display: Display::Flex,
-                            flex_direction: FlexDirection::Column,
-                            justify_content: JustifyContent::Center,
-                            align_items: AlignItems::Center,
+                        Text::default(),
+                        TextFont {
+                            font_size: 16.,
                             ..default()
                         },
-                        children![
-                            (
-                                ImageNode::new(asset_server.load("A.png")),
-                                Node {
-                                    width: px(40),
-                                    height: px(40),
-                                    margin: UiRect::left(px(60)),
-                                    ..default()
-                                }
-                            ),
-                            (
-                                Text::new("Heal CPU"),
-                                TextFont {
-                                    font_size: 10.,
-                                    ..default()
-                                },
-                                Node {
-                                    margin: UiRect::left(px(60)),
-                                    ..default()
-                                }
-                            )
-                        ]
+                        Binding::text(*progress, |countdown: &WaveCountdown, locale: &Locale| {
+                            match countdown {
+                                WaveCountdown::None => String::new(),
+                                WaveCountdown::Shop => locale.get("hud.next_wave_after_shop"),
+                                WaveCountdown::NextSpawn(secs) => locale.format(
+                                    "hud.next_spawn",
+                                    &[("seconds", &format!("{secs:.1}"))],
+                                ),
+                                WaveCountdown::LastEnemies => locale.get("hud.last_enemies"),
+                            }
+                        })
                     )
                 ]
             )
         ],
     ));
+
+    commands.spawn((
+        DespawnOnExit(AppState::InGame),
+        PlayerPanels,
+        Node {
+            position_type: PositionType::Absolute,
+            bottom: px(10),
+            left: px(10),
+            right: px(10),
+            display: Display::Flex,
+            justify_content: JustifyContent::SpaceEvenly,
+            align_items: AlignItems::End,
+            ..default()
+        },
+    ));
 }
@@ -0,0 +1,340 @@
+use std::{collections::HashMap, time::Duration};
+
+use bevy::prelude::*;
+
+use crate::{
+    Cpu,
+    plugins::{
+        health::Health,
+        locale::Locale,
+        net::SimulationSystems,
+        player::{Player, PlayerInput, ReadPlayerInput, ShootCooldown},
+        sound::{PlaySfx, Sfx},
+        wave::GamePhase,
+    },
+};
+
+pub struct UpgradePlugin;
+
+impl Plugin for UpgradePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_message::<UpgradePurchased>().add_systems(
+            Update,
+            (
+                handle_upgrades
+                    .after(ReadPlayerInput)
+                    .run_if(in_state(GamePhase::Combat)),
+                apply_upgrade_effects,
+            )
+                .chain()
+                .in_set(SimulationSystems),
+        );
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum UpgradeId {
+    FireRate,
+    Damage,
+    Speed,
+    HealCpu,
+    Magnet,
+}
+
+/// Player attributes that upgrades can modify.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Stat {
+    ShootCooldown,
+    Damage,
+    MoveSpeed,
+    /// Distance at which dropped pickups fly toward the player.
+    MagnetRadius,
+}
+
+impl Stat {
+    pub fn key(self) -> &'static str {
+        match self {
+            Stat::ShootCooldown => "stat.cooldown",
+            Stat::Damage => "stat.damage",
+            Stat::MoveSpeed => "stat.speed",
+            Stat::MagnetRadius => "stat.magnet",
+        }
+    }
+
+    fn format(self, value: f32) -> String {
+        match self {
+            Stat::ShootCooldown => format!("{value:.2}s"),
+            Stat::Damage | Stat::MoveSpeed | Stat::MagnetRadius => format!("{value:.1}"),
+        }
+    }
+
+    fn base(self) -> f32 {
+        match self {
+            Stat::ShootCooldown => 0.5,
+            Stat::Damage => 10.,
+            Stat::MoveSpeed => 2.,
+            Stat::MagnetRadius => 1.5,
+        }
+    }
+
+    fn min(self) -> f32 {
+        match self {
+            Stat::ShootCooldown => 0.05,
+            Stat::Damage | Stat::MoveSpeed | Stat::MagnetRadius => 0.,
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+pub enum CostCurve {
+    /// Adds a fixed amount of coins per level owned.
+    Linear(u32),
+    /// Multiplies the base cost by the given factor per level owned.
+    Exponential(f32),
+}
+
+#[derive(Clone, Copy)]
+pub enum UpgradeEffect {
+    /// Adds `per_level` to `stat` for every level owned.
+    Stat { stat: Stat, per_level: f32 },
+    /// Restores CPU health once, on purchase.
+    HealCpu(f32),
+}
+
+pub struct UpgradeDef {
+    pub id: UpgradeId,
+    pub name_key: &'static str,
+    /// Face button buying this upgrade during combat, if any, shown in the radial HUD.
+    pub button: Option<GamepadButton>,
+    pub base_cost: u32,
+    pub cost_curve: CostCurve,
+    /// `None` means the upgrade can be bought indefinitely.
+    pub max_level: Option<u32>,
+    pub effect: UpgradeEffect,
+}
+
+pub const UPGRADES: &[UpgradeDef] = &[
+    UpgradeDef {
+        id: UpgradeId::FireRate,
+        name_key: "upgrade.fire_rate",
+        button: Some(GamepadButton::West),
+        base_cost: 10,
+        cost_curve: CostCurve::Exponential(1.5),
+        max_level: Some(5),
+        effect: UpgradeEffect::Stat {
+            stat: Stat::ShootCooldown,
+            per_level: -0.08,
+        },
+    },
+    UpgradeDef {
+        id: UpgradeId::Damage,
+        name_key: "upgrade.damage",
+        button: Some(GamepadButton::North),
+        base_cost: 10,
+        cost_curve: CostCurve::Exponential(1.5),
+        max_level: Some(10),
+        effect: UpgradeEffect::Stat {
+            stat: Stat::Damage,
+            per_level: 5.,
+        },
+    },
+    UpgradeDef {
+        id: UpgradeId::Speed,
+        name_key: "upgrade.speed",
+        button: Some(GamepadButton::South),
+        base_cost: 10,
+        cost_curve: CostCurve::Linear(5),
+        max_level: Some(6),
+        effect: UpgradeEffect::Stat {
+            stat: Stat::MoveSpeed,
+            per_level: 0.5,
+        },
+    },
+    UpgradeDef {
+        id: UpgradeId::HealCpu,
+        name_key: "upgrade.heal_cpu",
+        button: Some(GamepadButton::East),
+        base_cost: 10,
+        cost_curve: CostCurve::Linear(0),
+        max_level: None,
+        effect: UpgradeEffect::HealCpu(10.),
+    },
+    UpgradeDef {
+        id: UpgradeId::Magnet,
+        name_key: "upgrade.magnet",
+        button: None,
+        base_cost: 15,
+        cost_curve: CostCurve::Linear(10),
+        max_level: Some(4),
+        effect: UpgradeEffect::Stat {
+            stat: Stat::MagnetRadius,
+            per_level: 1.,
+        },
+    },
+];
+
+impl UpgradeDef {
+    pub fn get(id: UpgradeId) -> &'static UpgradeDef {
+        UPGRADES
+            .iter()
+            .find(|def| def.id == id)
+            .expect("every upgrade id has a definition")
+    }
+
+    /// Position of `id` in [`UPGRADES`], stable across builds of the same version.
+    pub fn index(id: UpgradeId) -> usize {
+        UPGRADES
+            .iter()
+            .position(|def| def.id == id)
+            .expect("every upgrade id has a definition")
+    }
+
+    /// Cost of buying the next level when `level` levels are owned, or `None` once maxed.
+    pub fn cost(&self, level: u32) -> Option<u32> {
+        if self.max_level.is_some_and(|max| level >= max) {
+            return None;
+        }
+        Some(match self.cost_curve {
+            CostCurve::Linear(step) => self.base_cost + step * level,
+            CostCurve::Exponential(growth) => {
+                (self.base_cost as f32 * growth.powi(level as i32)).round() as u32
+            }
+        })
+    }
+
+    /// Describes what buying the next level changes, e.g. `Damage 15.0 -> 20.0`.
+    pub fn preview(&self, levels: &UpgradeLevels, locale: &Locale) -> String {
+        match self.effect {
+            UpgradeEffect::Stat { stat, .. } => {
+                let mut next = levels.clone();
+                *next.0.entry(self.id).or_default() += 1;
+                locale.format(
+                    "upgrade.preview",
+                    &[
+                        ("stat", &locale.get(stat.key())),
+                        ("from", &stat.format(levels.stat(stat))),
+                        ("to", &stat.format(next.stat(stat))),
+                    ],
+                )
+            }
+            UpgradeEffect::HealCpu(amount) => {
+                locale.format("upgrade.preview_heal", &[("amount", &amount)])
+            }
+        }
+    }
+}
+
+/// Sent once an upgrade level has been paid for, so its effect can be applied.
+#[derive(Message)]
+pub struct UpgradePurchased {
+    pub player: Entity,
+    pub id: UpgradeId,
+    /// Coins paid for it.
+    pub cost: u32,
+}
+
+#[derive(Default, Clone)]
+pub struct UpgradeLevels(HashMap<UpgradeId, u32>);
+
+impl UpgradeLevels {
+    pub fn level(&self, id: UpgradeId) -> u32 {
+        self.0.get(&id).copied().unwrap_or(0)
+    }
+
+    pub fn set_level(&mut self, id: UpgradeId, level: u32) {
+        self.0.insert(id, level);
+    }
+
+    pub fn stat(&self, stat: Stat) -> f32 {
+        let bonus: f32 = UPGRADES
+            .iter()
+            .filter_map(|def| match def.effect {
+                UpgradeEffect::Stat {
+                    stat: affected,
+                    per_level,
+                } if affected == stat => Some(per_level * self.level(def.id) as f32),
+                _ => None,
+            })
+            .sum();
+        (stat.base() + bonus).max(stat.min())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PurchaseError {
+    MaxLevel,
+    NotEnoughCoins { cost: u32 },
+}
+
+impl Player {
+    /// Price of the next level of `def`, if this player can buy it.
+    pub fn purchase_cost(&self, def: &UpgradeDef) -> Result<u32, PurchaseError> {
+        let cost = def
+            .cost(self.upgrades.level(def.id))
+            .ok_or(PurchaseError::MaxLevel)?;
+        if self.coins < cost {
+            return Err(PurchaseError::NotEnoughCoins { cost });
+        }
+        Ok(cost)
+    }
+
+    /// Pays for and grants the next level of `def`, returning what it cost.
+    pub fn purchase(&mut self, def: &UpgradeDef) -> Result<u32, PurchaseError> {
+        let cost = self.purchase_cost(def)?;
+        self.coins -= cost;
+        *self.upgrades.0.entry(def.id).or_default() += 1;
+        Ok(cost)
+    }
+}
+
+pub fn shoot_cooldown_timer(levels: &UpgradeLevels) -> Timer {
+    Timer::new(
+        Duration::from_secs_f32(levels.stat(Stat::ShootCooldown)),
+        TimerMode::Once,
+    )
+}
+
+fn handle_upgrades(
+    mut player_query: Query<(Entity, &mut Player, &PlayerInput)>,
+    mut purchases: MessageWriter<UpgradePurchased>,
+) {
+    for (entity, mut player, input) in &mut player_query {
+        let Some(id) = input.purchase else {
+            continue;
+        };
+        if let Ok(cost) = player.purchase(UpgradeDef::get(id)) {
+            purchases.write(UpgradePurchased {
+                player: entity,
+                id,
+                cost,
+            });
+        }
+    }
+}
+
+fn apply_upgrade_effects(
+    mut purchases: MessageReader<UpgradePurchased>,
+    mut player_query: Query<(&Player, &mut ShootCooldown)>,
+    mut cpu_health: Single<&mut Health, With<Cpu>>,
+    mut sfx: MessageWriter<PlaySfx>,
+) {
+    for purchase in purchases.read() {
+        let Ok((player, mut cooldown)) = player_query.get_mut(purchase.player) else {
+            continue;
+        };
+
+        match UpgradeDef::get(purchase.id).effect {
+            UpgradeEffect::Stat {
+                stat: Stat::ShootCooldown,
+                ..
+            } => {
+                cooldown.0 = shoot_cooldown_timer(&player.upgrades);
+            }
+            UpgradeEffect::Stat { .. } => {}
+            UpgradeEffect::HealCpu(amount) => {
+                cpu_health.0 = (cpu_health.0 + amount).min(100.);
+            }
+        }
+        sfx.write(PlaySfx(Sfx::LevelUp));
+    }
+}
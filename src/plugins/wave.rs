@@ -0,0 +1,177 @@
+use std::{collections::VecDeque, time::Duration};
+
+use bevy::prelude::*;
+
+use crate::{
+    AppState,
+    plugins::{
+        endless::{EndlessSeed, GameMode, plan_endless_wave},
+        enemy::{Enemy, EnemyKind, EnemySpawner},
+        net::{NetRole, SimulationSystems},
+        pickup::Pickup,
+        tutorial::tutorial_in_progress,
+    },
+};
+
+pub struct WavePlugin;
+
+#[derive(SubStates, Default, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[source(AppState = AppState::InGame)]
+pub enum GamePhase {
+    #[default]
+    Combat,
+    /// Intermission between two waves, spawning is paused.
+    Shop,
+}
+
+#[derive(Resource)]
+pub struct Wave {
+    pub number: u32,
+}
+
+impl Default for Wave {
+    fn default() -> Self {
+        Self { number: 1 }
+    }
+}
+
+/// Enemies left in the wave, whether still queued or already out.
+#[derive(Component, Default, PartialEq)]
+pub struct WaveEnemies(pub usize);
+
+/// What the wave waits on, shown under the enemy count.
+#[derive(Component, Default, PartialEq)]
+pub enum WaveCountdown {
+    /// Nothing to announce, such as on a client which does not see the spawners.
+    #[default]
+    None,
+    Shop,
+    /// Seconds until the next spawner releases an enemy.
+    NextSpawn(f32),
+    /// Every enemy of the wave is out.
+    LastEnemies,
+}
+
+impl Plugin for WavePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_sub_state::<GamePhase>()
+            .init_resource::<Wave>()
+            .add_systems(OnEnter(AppState::InGame), (reset_wave, spawn_wave_progress))
+            .add_systems(
+                Update,
+                track_wave_progress.run_if(in_state(AppState::InGame)),
+            )
+            .add_systems(
+                Update,
+                check_wave_cleared
+                    .in_set(SimulationSystems)
+                    .run_if(in_state(GamePhase::Combat))
+                    .run_if(not(tutorial_in_progress)),
+            )
+            .add_systems(OnEnter(GamePhase::Combat), prepare_wave)
+            .add_systems(OnExit(GamePhase::Shop), advance_wave);
+    }
+}
+
+/// Opens the shop once every enemy of the wave is dead and its drops are collected or expired.
+fn check_wave_cleared(
+    spawners: Query<&EnemySpawner>,
+    enemies: Query<(), With<Enemy>>,
+    pickups: Query<(), With<Pickup>>,
+    mut next_phase: ResMut<NextState<GamePhase>>,
+) {
+    if spawners.iter().all(|spawner| spawner.queue.is_empty())
+        && enemies.is_empty()
+        && pickups.is_empty()
+    {
+        next_phase.set(GamePhase::Shop);
+    }
+}
+
+fn reset_wave(mut wave: ResMut<Wave>) {
+    *wave = Wave::default();
+}
+
+/// Holds the progress of the wave for the HUD to bind to.
+pub fn spawn_wave_progress(mut commands: Commands) {
+    commands.spawn((
+        DespawnOnExit(AppState::InGame),
+        WaveEnemies::default(),
+        WaveCountdown::default(),
+    ));
+}
+
+/// Counts what is left of the wave and how long until the next spawn.
+fn track_wave_progress(
+    role: Res<NetRole>,
+    phase: Res<State<GamePhase>>,
+    spawners: Query<&EnemySpawner>,
+    enemies: Query<(), With<EnemyKind>>,
+    mut progress: Single<(&mut WaveEnemies, &mut WaveCountdown)>,
+) {
+    // Spawners only run on the host, a client just sees the enemies it was sent.
+    let spawning = !role.is_client();
+    let queued: usize = if spawning {
+        spawners.iter().map(|spawner| spawner.queue.len()).sum()
+    } else {
+        0
+    };
+    let next_spawn = spawners
+        .iter()
+        .filter(|spawner| spawning && !spawner.queue.is_empty())
+        .map(|spawner| spawner.timer.remaining_secs())
+        .reduce(f32::min);
+
+    let (enemies_left, countdown) = &mut *progress;
+    enemies_left.set_if_neq(WaveEnemies(queued + enemies.iter().count()));
+    countdown.set_if_neq(match (phase.get(), next_spawn) {
+        (GamePhase::Shop, _) => WaveCountdown::Shop,
+        (GamePhase::Combat, Some(secs)) => WaveCountdown::NextSpawn(secs),
+        (GamePhase::Combat, None) if spawning => WaveCountdown::LastEnemies,
+        (GamePhase::Combat, None) => WaveCountdown::None,
+    });
+}
+
+fn advance_wave(mut wave: ResMut<Wave>) {
+    wave.number += 1;
+}
+
+/// Fills every spawner's queue for the wave about to start.
+fn prepare_wave(
+    wave: Res<Wave>,
+    mode: Res<GameMode>,
+    seed: Res<EndlessSeed>,
+    mut spawners: Query<&mut EnemySpawner>,
+) {
+    // Sorted so that a seed plans the same wave whatever the query order is.
+    let mut spawners: Vec<_> = spawners.iter_mut().collect();
+    // Positions come from the level data, `total_cmp` keeps a stray NaN from panicking.
+    spawners.sort_by(|a, b| {
+        a.position
+            .x
+            .total_cmp(&b.position.x)
+            .then(a.position.z.total_cmp(&b.position.z))
+    });
+
+    for spawner in &mut spawners {
+        let interval = spawner.interval;
+        spawner
+            .timer
+            .set_duration(Duration::from_secs_f32(interval));
+        spawner.timer.reset();
+        spawner.route = spawner.path.clone();
+        spawner.queue.clear();
+    }
+
+    match *mode {
+        // Fed one enemy at a time by the tutorial script.
+        GameMode::Tutorial if wave.number == 1 => {}
+        GameMode::Standard | GameMode::Tutorial => {
+            for spawner in &mut spawners {
+                let count = spawner.wave_size(wave.number);
+                spawner.queue = VecDeque::from(vec![EnemyKind::Grunt; count as usize]);
+            }
+        }
+        GameMode::Endless => plan_endless_wave(seed.0, wave.number, &mut spawners),
+    }
+}